@@ -0,0 +1,835 @@
+use crate::components::memory::Memory;
+use std::collections::HashMap;
+use std::fmt;
+
+/** A problem in the source, `line` and `column` count from 1 */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+impl std::error::Error for AsmError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Number(i64),
+    Punct(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+const PUNCTS: [&str; 18] = [
+    "<<", ">>", ":", ",", "[", "]", "(", ")", "+", "-", "*", "/", "%", "&", "|", "^", "~", "=",
+];
+
+/** Splits a line into tokens, dropping the `;` comment at the end */
+fn tokenize(source: &str, line: usize) -> Result<Vec<Token>, AsmError> {
+    let chars: Vec<char> = source.chars().collect();
+    let error = |column: usize, message: String| AsmError {
+        line,
+        column: column + 1,
+        message,
+    };
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == ';' {
+            break;
+        }
+
+        let kind = if c.is_ascii_alphabetic() || c == '_' || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "_.".contains(chars[i])) {
+                i += 1;
+            }
+            TokenKind::Ident(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() || ((c == '#' || c == '$') && i + 1 < chars.len()) {
+            let (radix, digits_start) = match (c, chars.get(i + 1)) {
+                ('#' | '$', _) => (16, i + 1),
+                ('0', Some('x' | 'X')) => (16, i + 2),
+                ('0', Some('b' | 'B')) => (2, i + 2),
+                _ => (10, i),
+            };
+            i = digits_start;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let digits: String = chars[digits_start..i]
+                .iter()
+                .filter(|c| **c != '_')
+                .collect();
+            match i64::from_str_radix(&digits, radix) {
+                Ok(value) if !digits.is_empty() => TokenKind::Number(value),
+                _ => {
+                    let text: String = chars[start..i].iter().collect();
+                    return Err(error(start, format!("Invalid number '{}'", text)));
+                }
+            }
+        } else if c == '\'' {
+            match (chars.get(i + 1), chars.get(i + 2)) {
+                (Some(value), Some('\'')) if value.is_ascii() => {
+                    i += 3;
+                    TokenKind::Number(*value as i64)
+                }
+                _ => {
+                    return Err(error(
+                        start,
+                        "Invalid character, expected e.g. 'A'".to_string(),
+                    ))
+                }
+            }
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            match PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+                Some(punct) => {
+                    i += punct.len();
+                    TokenKind::Punct(punct)
+                }
+                None => return Err(error(start, format!("Unexpected character '{}'", c))),
+            }
+        };
+        tokens.push(Token {
+            kind,
+            column: start + 1,
+        });
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(i64),
+    Symbol {
+        name: String,
+        column: usize,
+    },
+    Unary {
+        op: &'static str,
+        value: Box<Expr>,
+    },
+    Binary {
+        op: &'static str,
+        column: usize,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+/** Binary operators from the loosest to the tightest binding */
+const PRECEDENCE: [&[&str]; 6] = [
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+#[derive(Debug, Clone)]
+enum OperandKind {
+    Register(u8),
+    /** `Vx-Vy` of 5XY2/5XY3 */
+    Range(u8, u8),
+    I,
+    /** `[I]` */
+    IndirectI,
+    Delay,
+    Sound,
+    Key,
+    Font,
+    BigFont,
+    Bcd,
+    Flags,
+    Pitch,
+    Long(Expr),
+    Value(Expr),
+}
+
+#[derive(Debug, Clone)]
+struct Operand {
+    kind: OperandKind,
+    column: usize,
+}
+
+/** Names with a meaning of their own in operands, which can't be labels or constants */
+const RESERVED: [&str; 10] = ["I", "DT", "ST", "K", "F", "HF", "B", "R", "PITCH", "LONG"];
+
+fn register(name: &str) -> Option<u8> {
+    match name.as_bytes() {
+        [b'v' | b'V', digit] => (*digit as char).to_digit(16).map(|x| x as u8),
+        _ => None,
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    line: usize,
+    /** Column right after the last token, where errors about a missing token point */
+    end: usize,
+}
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|token| token.column)
+            .unwrap_or(self.end)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+    fn error(&self, column: usize, message: String) -> AsmError {
+        AsmError {
+            line: self.line,
+            column,
+            message,
+        }
+    }
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(TokenKind::Punct(p)) if *p == punct) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+    fn expect(&mut self, punct: &str) -> Result<(), AsmError> {
+        if !self.eat(punct) {
+            return Err(self.error(self.column(), format!("Expected '{}'", punct)));
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, level: usize) -> Result<Expr, AsmError> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+
+        let mut left = self.expr(level + 1)?;
+        while let Some(TokenKind::Punct(op)) = self.peek() {
+            let op = *op;
+            if !PRECEDENCE[level].contains(&op) {
+                break;
+            }
+            let column = self.column();
+            self.position += 1;
+            let right = self.expr(level + 1)?;
+            left = Expr::Binary {
+                op,
+                column,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+
+        Ok(left)
+    }
+    fn unary(&mut self) -> Result<Expr, AsmError> {
+        let column = self.column();
+        match self.next().map(|token| token.kind) {
+            Some(TokenKind::Number(value)) => Ok(Expr::Number(value)),
+            Some(TokenKind::Ident(name)) => Ok(Expr::Symbol { name, column }),
+            Some(TokenKind::Punct("(")) => {
+                let value = self.expr(0)?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(TokenKind::Punct(op @ ("-" | "~" | "+"))) => Ok(Expr::Unary {
+                op,
+                value: Box::new(self.unary()?),
+            }),
+            _ => Err(self.error(column, "Expected a value".to_string())),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, AsmError> {
+        let column = self.column();
+        let kind = match self.peek().cloned() {
+            Some(TokenKind::Punct("[")) => {
+                self.position += 1;
+                match self.next().map(|token| token.kind) {
+                    Some(TokenKind::Ident(name)) if name.eq_ignore_ascii_case("I") => {}
+                    _ => return Err(self.error(column, "Expected '[I]'".to_string())),
+                }
+                self.expect("]")?;
+                OperandKind::IndirectI
+            }
+            Some(TokenKind::Ident(name)) if register(&name).is_some() => {
+                self.position += 1;
+                let x = register(&name).unwrap_or(0);
+                let range_end = match (self.peek(), self.tokens.get(self.position + 1)) {
+                    (
+                        Some(TokenKind::Punct("-")),
+                        Some(Token {
+                            kind: TokenKind::Ident(end),
+                            ..
+                        }),
+                    ) => register(end),
+                    _ => None,
+                };
+                match range_end {
+                    Some(y) => {
+                        self.position += 2;
+                        OperandKind::Range(x, y)
+                    }
+                    None => OperandKind::Register(x),
+                }
+            }
+            Some(TokenKind::Ident(name)) if RESERVED.contains(&name.to_uppercase().as_str()) => {
+                self.position += 1;
+                match name.to_uppercase().as_str() {
+                    "I" => OperandKind::I,
+                    "DT" => OperandKind::Delay,
+                    "ST" => OperandKind::Sound,
+                    "K" => OperandKind::Key,
+                    "F" => OperandKind::Font,
+                    "HF" => OperandKind::BigFont,
+                    "B" => OperandKind::Bcd,
+                    "R" => OperandKind::Flags,
+                    "PITCH" => OperandKind::Pitch,
+                    _ => OperandKind::Long(self.expr(0)?),
+                }
+            }
+            _ => OperandKind::Value(self.expr(0)?),
+        };
+
+        Ok(Operand { kind, column })
+    }
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction {
+        mnemonic: String,
+        column: usize,
+        operands: Vec<Operand>,
+    },
+    /** `DB` with a width of 1 or `DW` with a width of 2 */
+    Data {
+        width: u8,
+        values: Vec<(Expr, usize)>,
+    },
+}
+impl Statement {
+    fn size(&self) -> usize {
+        match self {
+            Statement::Instruction { operands, .. } => {
+                if operands
+                    .iter()
+                    .any(|operand| matches!(operand.kind, OperandKind::Long(_)))
+                {
+                    4
+                } else {
+                    2
+                }
+            }
+            Statement::Data { width, values } => *width as usize * values.len(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Symbol {
+    Label(u16),
+    Constant { value: Expr, line: usize },
+}
+
+/** Labels and constants, with constants evaluated when they're used so they can refer to anything */
+struct Symbols {
+    symbols: HashMap<String, Symbol>,
+}
+impl Symbols {
+    fn define(
+        &mut self,
+        name: String,
+        symbol: Symbol,
+        line: usize,
+        column: usize,
+    ) -> Result<(), AsmError> {
+        if register(&name).is_some() || RESERVED.contains(&name.to_uppercase().as_str()) {
+            return Err(AsmError {
+                line,
+                column,
+                message: format!("'{}' is reserved", name),
+            });
+        }
+        if self.symbols.contains_key(&name) {
+            return Err(AsmError {
+                line,
+                column,
+                message: format!("'{}' is already defined", name),
+            });
+        }
+        self.symbols.insert(name, symbol);
+
+        Ok(())
+    }
+
+    /** `resolving` holds the constants being evaluated, to catch ones that refer to themselves */
+    fn eval(&self, expr: &Expr, line: usize, resolving: &mut Vec<String>) -> Result<i64, AsmError> {
+        match expr {
+            Expr::Number(value) => Ok(*value),
+            Expr::Symbol { name, column } => match self.symbols.get(name) {
+                Some(Symbol::Label(address)) => Ok(*address as i64),
+                Some(Symbol::Constant {
+                    value,
+                    line: defined,
+                }) => {
+                    if resolving.contains(name) {
+                        return Err(AsmError {
+                            line,
+                            column: *column,
+                            message: format!("'{}' refers to itself", name),
+                        });
+                    }
+                    resolving.push(name.clone());
+                    let result = self.eval(value, *defined, resolving);
+                    resolving.pop();
+                    result
+                }
+                None => Err(AsmError {
+                    line,
+                    column: *column,
+                    message: format!("Undefined symbol '{}'", name),
+                }),
+            },
+            Expr::Unary { op, value } => {
+                let value = self.eval(value, line, resolving)?;
+                Ok(match *op {
+                    "-" => value.wrapping_neg(),
+                    "~" => !value,
+                    _ => value,
+                })
+            }
+            Expr::Binary {
+                op,
+                column,
+                left,
+                right,
+            } => {
+                let left = self.eval(left, line, resolving)?;
+                let right = self.eval(right, line, resolving)?;
+                match *op {
+                    "/" | "%" if right == 0 => Err(AsmError {
+                        line,
+                        column: *column,
+                        message: "Division by zero".to_string(),
+                    }),
+                    "/" => Ok(left.wrapping_div(right)),
+                    "%" => Ok(left.wrapping_rem(right)),
+                    "*" => Ok(left.wrapping_mul(right)),
+                    "+" => Ok(left.wrapping_add(right)),
+                    "-" => Ok(left.wrapping_sub(right)),
+                    "<<" => Ok(left.wrapping_shl(right as u32)),
+                    ">>" => Ok(left.wrapping_shr(right as u32)),
+                    "&" => Ok(left & right),
+                    "^" => Ok(left ^ right),
+                    _ => Ok(left | right),
+                }
+            }
+        }
+    }
+}
+
+/** Assembles the source for a ROM loaded at `Memory::ROM_BEGIN_INDEX`, see `assemble_at` */
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    assemble_at(source, Memory::ROM_BEGIN_INDEX)
+}
+
+/** Assembles mnemonic source in the style of Cowgod's reference into a ROM loaded at `address` */
+/** Each line is an optional `label:` followed by an instruction, `DB`/`DW` with comma-separated values, or `NAME EQU value` */
+/** Values are expressions of numbers (`42`, `#2A`, `$2A`, `0x2A`, `0b101010`, `'*'`), labels and constants */
+pub fn assemble_at(source: &str, address: u16) -> Result<Vec<u8>, AsmError> {
+    let mut symbols = Symbols {
+        symbols: HashMap::new(),
+    };
+    let mut statements = Vec::new();
+    let mut next_address = address as usize;
+
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let tokens = tokenize(text, line)?;
+        let end = text.chars().count() + 1;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            line,
+            end,
+        };
+
+        if let (Some(TokenKind::Ident(name)), Some(TokenKind::Punct(":"))) = (
+            parser.peek().cloned(),
+            parser.tokens.get(1).map(|token| token.kind.clone()),
+        ) {
+            if next_address > u16::MAX as usize {
+                return Err(parser.error(1, "Program doesn't fit in 64 KB".to_string()));
+            }
+            let column = parser.column();
+            symbols.define(name, Symbol::Label(next_address as u16), line, column)?;
+            parser.position += 2;
+        }
+
+        let column = parser.column();
+        let mnemonic = match parser.next().map(|token| token.kind) {
+            None => continue,
+            Some(TokenKind::Ident(mnemonic)) => mnemonic,
+            Some(_) => return Err(parser.error(column, "Expected a mnemonic".to_string())),
+        };
+
+        let is_constant = match parser.peek() {
+            Some(TokenKind::Punct("=")) => true,
+            Some(TokenKind::Ident(equ)) => equ.eq_ignore_ascii_case("EQU"),
+            _ => false,
+        };
+        let statement = if is_constant {
+            parser.position += 1;
+            let value = parser.expr(0)?;
+            if parser.peek().is_some() {
+                return Err(
+                    parser.error(parser.column(), "Expected the end of the line".to_string())
+                );
+            }
+            symbols.define(mnemonic, Symbol::Constant { value, line }, line, column)?;
+            continue;
+        } else if mnemonic.eq_ignore_ascii_case("DB") || mnemonic.eq_ignore_ascii_case("DW") {
+            let mut values = Vec::new();
+            loop {
+                let value_column = parser.column();
+                values.push((parser.expr(0)?, value_column));
+                if !parser.eat(",") {
+                    break;
+                }
+            }
+            Statement::Data {
+                width: if mnemonic.eq_ignore_ascii_case("DB") {
+                    1
+                } else {
+                    2
+                },
+                values,
+            }
+        } else {
+            let mut operands = Vec::new();
+            if parser.peek().is_some() {
+                loop {
+                    operands.push(parser.operand()?);
+                    if !parser.eat(",") {
+                        break;
+                    }
+                }
+            }
+            Statement::Instruction {
+                mnemonic: mnemonic.to_uppercase(),
+                column,
+                operands,
+            }
+        };
+        if parser.peek().is_some() {
+            return Err(parser.error(
+                parser.column(),
+                "Expected ',' or the end of the line".to_string(),
+            ));
+        }
+
+        let size = statement.size();
+        if next_address + size > u16::MAX as usize + 1 {
+            return Err(parser.error(column, "Program doesn't fit in 64 KB".to_string()));
+        }
+        statements.push((line, statement));
+        next_address += size;
+    }
+
+    let mut rom = Vec::with_capacity(next_address - address as usize);
+    for (line, statement) in &statements {
+        match statement {
+            Statement::Instruction {
+                mnemonic,
+                column,
+                operands,
+            } => {
+                let words = encode(mnemonic, *column, operands, &symbols, *line)?;
+                for word in words {
+                    rom.extend(word.to_be_bytes());
+                }
+            }
+            Statement::Data { width, values } => {
+                for (value, column) in values {
+                    let value = symbols.eval(value, *line, &mut Vec::new())?;
+                    match width {
+                        1 => rom.push(check(value, -0x80, 0xFF, "a byte", *line, *column)? as u8),
+                        _ => rom.extend(
+                            check(value, -0x8000, 0xFFFF, "a word", *line, *column)?.to_be_bytes(),
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(rom)
+}
+
+/** `value` if it's within `min..=max`, negative values become their two's complement */
+fn check(
+    value: i64,
+    min: i64,
+    max: i64,
+    what: &str,
+    line: usize,
+    column: usize,
+) -> Result<u16, AsmError> {
+    if value < min || value > max {
+        return Err(AsmError {
+            line,
+            column,
+            message: format!("{} doesn't fit in {}", value, what),
+        });
+    }
+    Ok((value & max) as u16)
+}
+
+/** The opcode words of one instruction */
+fn encode(
+    mnemonic: &str,
+    column: usize,
+    operands: &[Operand],
+    symbols: &Symbols,
+    line: usize,
+) -> Result<Vec<u16>, AsmError> {
+    let value = |operand: &Operand, min: i64, max: i64, what: &str| -> Result<u16, AsmError> {
+        match &operand.kind {
+            OperandKind::Value(expr) | OperandKind::Long(expr) => {
+                let value = symbols.eval(expr, line, &mut Vec::new())?;
+                check(value, min, max, what, line, operand.column)
+            }
+            _ => Err(AsmError {
+                line,
+                column: operand.column,
+                message: format!("Expected {}", what),
+            }),
+        }
+    };
+    let nibble = |operand: &Operand| value(operand, 0, 0xF, "a nibble");
+    let byte = |operand: &Operand| value(operand, -0x80, 0xFF, "a byte");
+    let address = |operand: &Operand| value(operand, 0, 0xFFF, "a 12-bit address");
+    let xy = |x: u8, y: u8| (x as u16) << 8 | (y as u16) << 4;
+
+    use OperandKind::*;
+    let kinds: Vec<&OperandKind> = operands.iter().map(|operand| &operand.kind).collect();
+    let words = match (mnemonic, kinds.as_slice()) {
+        ("CLS", []) => vec![0x00E0],
+        ("RET", []) => vec![0x00EE],
+        ("SCD", [Value(_)]) => vec![0x00C0 | nibble(&operands[0])?],
+        ("SCU", [Value(_)]) => vec![0x00D0 | nibble(&operands[0])?],
+        ("SCR", []) => vec![0x00FB],
+        ("SCL", []) => vec![0x00FC],
+        ("EXIT", []) => vec![0x00FD],
+        ("LOW", []) => vec![0x00FE],
+        ("HIGH", []) => vec![0x00FF],
+        ("JP", [Value(_)]) => vec![0x1000 | address(&operands[0])?],
+        ("JP", [Register(0), Value(_)]) => vec![0xB000 | address(&operands[1])?],
+        ("CALL", [Value(_)]) => vec![0x2000 | address(&operands[0])?],
+        ("SE", [Register(x), Value(_)]) => vec![0x3000 | (*x as u16) << 8 | byte(&operands[1])?],
+        ("SNE", [Register(x), Value(_)]) => vec![0x4000 | (*x as u16) << 8 | byte(&operands[1])?],
+        ("SE", [Register(x), Register(y)]) => vec![0x5000 | xy(*x, *y)],
+        ("SNE", [Register(x), Register(y)]) => vec![0x9000 | xy(*x, *y)],
+        ("LD", [IndirectI, Range(x, y)]) => vec![0x5002 | xy(*x, *y)],
+        ("LD", [Range(x, y), IndirectI]) => vec![0x5003 | xy(*x, *y)],
+        ("LD", [Register(x), Value(_)]) => vec![0x6000 | (*x as u16) << 8 | byte(&operands[1])?],
+        ("ADD", [Register(x), Value(_)]) => vec![0x7000 | (*x as u16) << 8 | byte(&operands[1])?],
+        ("LD", [Register(x), Register(y)]) => vec![0x8000 | xy(*x, *y)],
+        ("OR", [Register(x), Register(y)]) => vec![0x8001 | xy(*x, *y)],
+        ("AND", [Register(x), Register(y)]) => vec![0x8002 | xy(*x, *y)],
+        ("XOR", [Register(x), Register(y)]) => vec![0x8003 | xy(*x, *y)],
+        ("ADD", [Register(x), Register(y)]) => vec![0x8004 | xy(*x, *y)],
+        ("SUB", [Register(x), Register(y)]) => vec![0x8005 | xy(*x, *y)],
+        ("SHR", [Register(x)]) => vec![0x8006 | xy(*x, *x)],
+        ("SHR", [Register(x), Register(y)]) => vec![0x8006 | xy(*x, *y)],
+        ("SUBN", [Register(x), Register(y)]) => vec![0x8007 | xy(*x, *y)],
+        ("SHL", [Register(x)]) => vec![0x800E | xy(*x, *x)],
+        ("SHL", [Register(x), Register(y)]) => vec![0x800E | xy(*x, *y)],
+        ("LD", [I, Value(_)]) => vec![0xA000 | address(&operands[1])?],
+        ("LD", [I, Long(_)]) => vec![0xF000, value(&operands[1], 0, 0xFFFF, "a 16-bit address")?],
+        ("RND", [Register(x), Value(_)]) => vec![0xC000 | (*x as u16) << 8 | byte(&operands[1])?],
+        ("DRW", [Register(x), Register(y), Value(_)]) => {
+            vec![0xD000 | xy(*x, *y) | nibble(&operands[2])?]
+        }
+        ("SKP", [Register(x)]) => vec![0xE09E | (*x as u16) << 8],
+        ("SKNP", [Register(x)]) => vec![0xE0A1 | (*x as u16) << 8],
+        ("PLANE", [Value(_)]) => vec![0xF001 | nibble(&operands[0])? << 8],
+        ("AUDIO", []) => vec![0xF002],
+        ("LD", [Register(x), Delay]) => vec![0xF007 | (*x as u16) << 8],
+        ("LD", [Register(x), Key]) => vec![0xF00A | (*x as u16) << 8],
+        ("LD", [Delay, Register(x)]) => vec![0xF015 | (*x as u16) << 8],
+        ("LD", [Sound, Register(x)]) => vec![0xF018 | (*x as u16) << 8],
+        ("ADD", [I, Register(x)]) => vec![0xF01E | (*x as u16) << 8],
+        ("LD", [Font, Register(x)]) => vec![0xF029 | (*x as u16) << 8],
+        ("LD", [BigFont, Register(x)]) => vec![0xF030 | (*x as u16) << 8],
+        ("LD", [Bcd, Register(x)]) => vec![0xF033 | (*x as u16) << 8],
+        ("LD", [Pitch, Register(x)]) => vec![0xF03A | (*x as u16) << 8],
+        ("LD", [IndirectI, Register(x)]) => vec![0xF055 | (*x as u16) << 8],
+        ("LD", [Register(x), IndirectI]) => vec![0xF065 | (*x as u16) << 8],
+        ("LD", [Flags, Register(x)]) => vec![0xF075 | (*x as u16) << 8],
+        ("LD", [Register(x), Flags]) => vec![0xF085 | (*x as u16) << 8],
+        _ => {
+            let known = [
+                "CLS", "RET", "SCD", "SCU", "SCR", "SCL", "EXIT", "LOW", "HIGH", "JP", "CALL",
+                "SE", "SNE", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND",
+                "DRW", "SKP", "SKNP", "PLANE", "AUDIO",
+            ];
+            let message = match known.contains(&mnemonic) {
+                true => format!("Invalid operands for {}", mnemonic),
+                false => format!("Unknown mnemonic '{}'", mnemonic),
+            };
+            return Err(AsmError {
+                line,
+                column,
+                message,
+            });
+        }
+    };
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, assemble_at, AsmError};
+    use crate::disasm::{Instruction, Syntax};
+    use crate::emulator::Emulator;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_assemble() {
+        // Arrange
+        let source = "
+            COUNT EQU 3          ; loops before stopping
+            START = $200
+        start:  LD V0, 0
+        loop:   ADD V0, 1
+                SE V0, COUNT
+                JP loop
+                LD I, data + 1
+                LD V1-V2, [I]
+        end:    JP end
+        data:   DB 'A', #42, 0b11 << 2, -1
+                DW end - START, $ABCD
+        ";
+
+        // Act
+        let rom = assemble(source).unwrap();
+        let mut emulator = Emulator::builder().build();
+        emulator.load_rom(rom.clone()).unwrap();
+        for _ in 0..20 {
+            emulator.cycle().unwrap();
+        }
+
+        // Assert
+        assert_eq!(
+            rom,
+            vec![
+                0x60, 0x00, 0x70, 0x01, 0x30, 0x03, 0x12, 0x02, 0xA2, 0x0F, 0x51, 0x23, 0x12, 0x0C,
+                0x41, 0x42, 0x0C, 0xFF, 0x00, 0x0C, 0xAB, 0xCD,
+            ]
+        );
+        assert_eq!(emulator.pc(), 0x20C);
+        assert_eq!(emulator.v()[0..3], [3, 0x42, 0x0C]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_round_trip() {
+        for opcode in 0..=u16::MAX {
+            // Arrange
+            let instruction = Instruction::decode(opcode);
+            if matches!(instruction, Instruction::Unknown { .. }) {
+                continue;
+            }
+
+            // Act
+            let rom = assemble(&instruction.mnemonic(Syntax::Cowgod)).unwrap();
+
+            // Assert
+            assert_eq!(rom.len(), instruction.size() as usize);
+            assert_eq!(
+                Instruction::decode(u16::from_be_bytes([rom[0], rom[1]])),
+                instruction,
+                "{:#06X}",
+                opcode
+            );
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_assemble_at() {
+        // Arrange
+        let source = "here: JP here\nLD I, LONG far\nfar:";
+
+        // Act
+        let rom = assemble_at(source, 0x600).unwrap();
+
+        // Assert
+        assert_eq!(rom, vec![0x16, 0x00, 0xF0, 0x00, 0x06, 0x06]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_errors() {
+        // Arrange
+        let sources = [
+            "CLS\n  FOO V0",
+            "LD V0, V1, V2",
+            "JP nowhere",
+            "LD V0, 256",
+            "ONE EQU TWO + 1\nTWO EQU ONE\nDB ONE",
+            "x: CLS\nx: CLS",
+            "DB 1 +",
+            "LD V0, 0x",
+        ];
+
+        // Act
+        let errors: Vec<AsmError> = sources
+            .iter()
+            .map(|source| assemble(source).unwrap_err())
+            .collect();
+
+        // Assert
+        let positions: Vec<(usize, usize, &str)> = errors
+            .iter()
+            .map(|err| (err.line, err.column, err.message.as_str()))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (2, 3, "Unknown mnemonic 'FOO'"),
+                (1, 1, "Invalid operands for LD"),
+                (1, 4, "Undefined symbol 'nowhere'"),
+                (1, 8, "256 doesn't fit in a byte"),
+                (2, 9, "'ONE' refers to itself"),
+                (2, 1, "'x' is already defined"),
+                (1, 7, "Expected a value"),
+                (1, 8, "Invalid number '0x'"),
+            ]
+        );
+        assert_eq!(errors[0].to_string(), "2:3: Unknown mnemonic 'FOO'");
+    }
+}
@@ -0,0 +1,23 @@
+use crate::components::keypad::Keypad;
+
+/** Receives finished frames from the processor's framebuffer */
+pub trait VideoSink {
+    /** `gfx` holds `width * height` pixels in row-major order, where 0 is dark and 1 is lit */
+    /** XO-CHIP programs can also draw 2 (plane 2 only) and 3 (both planes), so a 4-colour palette covers every program */
+    fn present(&mut self, gfx: &[u8], width: usize, height: usize);
+}
+
+/** Plays the beep while the sound timer is running */
+pub trait AudioSink {
+    /** Called after every cycle with whether the sound timer is non-zero */
+    fn set_playing(&mut self, playing: bool);
+    /** Called along with `set_playing` once an XO-CHIP program has set an audio pattern */
+    /** The 128 bits play MSB first at `sample_rate` per second, sinks that can't play them keep beeping */
+    fn set_pattern(&mut self, _pattern: &[u8; 16], _sample_rate: f64) {}
+}
+
+/** Feeds host input into the keypad */
+pub trait InputSource {
+    /** Called before every cycle to bring the keypad up to date with the host */
+    fn poll(&mut self, keypad: &mut Keypad);
+}
@@ -0,0 +1,53 @@
+use chip8_emulator::asm::assemble_at;
+use chip8_emulator::Memory;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: chip8-asm <source.asm> [--out <rom.ch8>] [--load-address <hex>]
+
+Writes the ROM next to the source with a .ch8 extension by default.";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let mut source_path = None;
+    let mut out = None;
+    let mut load_address = Memory::ROM_BEGIN_INDEX;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = Some(PathBuf::from(args.next().ok_or("Missing value for --out")?)),
+            "--load-address" => {
+                let value = args.next().ok_or("Missing value for --load-address")?;
+                load_address = u16::from_str_radix(value.trim_start_matches("0x"), 16)?;
+            }
+            "-h" | "--help" => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+            _ if source_path.is_none() => source_path = Some(PathBuf::from(arg)),
+            _ => {
+                eprintln!("Unexpected argument '{}'\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+    let Some(source_path) = source_path else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+    let out = out.unwrap_or_else(|| source_path.with_extension("ch8"));
+
+    let source = std::fs::read_to_string(&source_path)
+        .map_err(|err| format!("Failed to read '{}': {}", source_path.display(), err))?;
+    let rom = match assemble_at(&source, load_address) {
+        Ok(rom) => rom,
+        Err(err) => {
+            // path:line:column: message, which editors can jump to
+            eprintln!("{}:{}", source_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+    std::fs::write(&out, rom)
+        .map_err(|err| format!("Failed to write '{}': {}", out.display(), err))?;
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use chip8_emulator::disasm::disassemble;
+use chip8_emulator::Memory;
+use std::io::Write;
+
+const USAGE: &str = "Usage: chip8-disasm <rom.ch8> [--load-address <hex>]";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let mut rom_path = None;
+    let mut load_address = Memory::ROM_BEGIN_INDEX;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--load-address" => {
+                let value = args.next().ok_or("Missing value for --load-address")?;
+                load_address = u16::from_str_radix(value.trim_start_matches("0x"), 16)?;
+            }
+            "-h" | "--help" => {
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+            _ if rom_path.is_none() => rom_path = Some(arg),
+            _ => {
+                eprintln!("Unexpected argument '{}'\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+    let Some(rom_path) = rom_path else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+
+    let rom = std::fs::read(&rom_path)
+        .map_err(|err| format!("Failed to read '{}': {}", rom_path, err))?;
+    // Not print!, which panics when piped into e.g. head
+    std::io::stdout().write_all(disassemble(&rom, load_address).to_string().as_bytes())?;
+
+    Ok(())
+}
@@ -0,0 +1,346 @@
+use chip8_emulator::backend::{InputSource, VideoSink};
+use chip8_emulator::movie::Movie;
+use chip8_emulator::octo::RomFile;
+use chip8_emulator::{Emulator, Keypad, Memory, Platform, Preset, Quirks};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Usage: chip8-run <rom.ch8|source.8o> [options]
+
+Options:
+  --frames <n>              Number of 60 Hz frames to run (default 600, or the movie length)
+  --cycles <n>              Number of cycles to run instead of frames
+  --hz <n>                  Cycles per second (default 500)
+  --quirks <preset>         vip, chip48, schip10, schip11 or xochip (default schip11)
+  --load-address <hex>      Where the ROM loads and starts, 600 for ETI-660 programs (default 200)
+  --seed <n>                Seed for CXNN random numbers (default random)
+  --press <frame>:<key>[:<frames>]
+                            Hold hex key from a frame on, for 1 frame by default (repeatable)
+  --record <path>           Record the inputs into a movie file
+  --play <path>             Play back a movie file, taking over its settings and inputs
+  --every <n>               Also write every nth frame
+  --out <path>              Output file, .png or .pbm (default frame.png)
+  --scale <n>               Scale each pixel up to n x n (default 1)";
+
+struct KeyPress {
+    frame: u64,
+    key: u8,
+    frames: u64,
+}
+impl KeyPress {
+    fn parse(value: &str) -> Result<KeyPress, Box<dyn std::error::Error>> {
+        let parts: Vec<&str> = value.split(':').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(format!(
+                "Invalid --press '{}', expected <frame>:<key>[:<frames>]",
+                value
+            )
+            .into());
+        }
+
+        let key = u8::from_str_radix(parts[1], 16)?;
+        if key > 0xF {
+            return Err(format!("Invalid key '{}', expected 0-F", parts[1]).into());
+        }
+
+        Ok(KeyPress {
+            frame: parts[0].parse()?,
+            key,
+            frames: match parts.get(2) {
+                Some(frames) => frames.parse()?,
+                None => 1,
+            },
+        })
+    }
+}
+
+enum Format {
+    Png,
+    Pbm,
+}
+
+struct Options {
+    rom_path: String,
+    frames: Option<u64>,
+    cycles: Option<u64>,
+    hz: u32,
+    quirks: Quirks,
+    platform: Platform,
+    load_address: u16,
+    seed: Option<u64>,
+    record: Option<PathBuf>,
+    play: Option<PathBuf>,
+    presses: Vec<KeyPress>,
+    every: Option<u64>,
+    out: PathBuf,
+    format: Format,
+    scale: usize,
+}
+impl Options {
+    fn parse() -> Result<Options, Box<dyn std::error::Error>> {
+        let mut args = std::env::args().skip(1);
+        let mut rom_path = None;
+        let mut frames = None;
+        let mut cycles = None;
+        let mut hz = 500;
+        let mut quirks = Quirks::default();
+        let mut platform = Platform::default();
+        let mut load_address = Memory::ROM_BEGIN_INDEX;
+        let mut seed = None;
+        let mut record = None;
+        let mut play = None;
+        let mut presses = Vec::new();
+        let mut every = None;
+        let mut out = PathBuf::from("frame.png");
+        let mut scale = 1;
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+            match arg.as_str() {
+                "--frames" => frames = Some(value()?.parse()?),
+                "--cycles" => cycles = Some(value()?.parse()?),
+                "--hz" => hz = value()?.parse()?,
+                "--quirks" => {
+                    let name = value()?;
+                    let preset = Preset::from_name(&name)?;
+                    quirks = preset.quirks();
+                    platform = preset.platform();
+                }
+                "--load-address" => {
+                    load_address = u16::from_str_radix(value()?.trim_start_matches("0x"), 16)?
+                }
+                "--seed" => seed = Some(value()?.parse()?),
+                "--record" => record = Some(PathBuf::from(value()?)),
+                "--play" => play = Some(PathBuf::from(value()?)),
+                "--press" => presses.push(KeyPress::parse(&value()?)?),
+                "--every" => every = Some(value()?.parse()?),
+                "--out" => out = PathBuf::from(value()?),
+                "--scale" => scale = value()?.parse()?,
+                "-h" | "--help" => return Err(USAGE.into()),
+                _ if rom_path.is_none() => rom_path = Some(arg),
+                _ => return Err(format!("Unexpected argument '{}'\n{}", arg, USAGE).into()),
+            }
+        }
+
+        if hz < 60 {
+            return Err("--hz must be at least 60".into());
+        }
+        if every == Some(0) || scale == 0 {
+            return Err("--every and --scale must be greater than 0".into());
+        }
+        let format = match out.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => Format::Png,
+            Some("pbm") => Format::Pbm,
+            _ => return Err("--out must end in .png or .pbm".into()),
+        };
+
+        Ok(Options {
+            rom_path: rom_path.ok_or(USAGE)?,
+            frames,
+            cycles,
+            hz,
+            quirks,
+            platform,
+            load_address,
+            seed,
+            record,
+            play,
+            presses,
+            every,
+            out,
+            format,
+            scale,
+        })
+    }
+}
+
+/** Replays `--press` arguments against the current frame number */
+struct ScriptedInput {
+    presses: Vec<KeyPress>,
+    frame: u64,
+}
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        keypad.release_all();
+        for press in &self.presses {
+            if (press.frame..press.frame + press.frames).contains(&self.frame) {
+                keypad.press(press.key);
+            }
+        }
+    }
+}
+
+/** Writes every presented frame to an image file */
+struct ImageWriter {
+    path: PathBuf,
+    format: Format,
+    scale: usize,
+    error: Option<std::io::Error>,
+}
+impl ImageWriter {
+    fn write_pbm(
+        path: &Path,
+        gfx: &[u8],
+        width: usize,
+        height: usize,
+        scale: usize,
+    ) -> std::io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "P1\n{} {}", width * scale, height * scale)?;
+
+        for row in 0..height * scale {
+            let line: Vec<&str> = (0..width * scale)
+                .map(|col| match gfx[row / scale * width + col / scale] {
+                    0 => "0",
+                    _ => "1",
+                })
+                .collect();
+            writeln!(file, "{}", line.join(" "))?;
+        }
+
+        file.flush()
+    }
+    fn write_png(
+        path: &Path,
+        gfx: &[u8],
+        width: usize,
+        height: usize,
+        scale: usize,
+    ) -> std::io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, (width * scale) as u32, (height * scale) as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let pixels: Vec<u8> = (0..height * scale)
+            .flat_map(|row| {
+                // Dark, plane 1, plane 2 and both planes
+                (0..width * scale).map(move |col| match gfx[row / scale * width + col / scale] {
+                    0 => 0x00,
+                    1 => 0xFF,
+                    2 => 0xAA,
+                    _ => 0x55,
+                })
+            })
+            .collect();
+
+        encoder.write_header()?.write_image_data(&pixels)?;
+        Ok(())
+    }
+}
+impl VideoSink for ImageWriter {
+    fn present(&mut self, gfx: &[u8], width: usize, height: usize) {
+        let result = match self.format {
+            Format::Png => ImageWriter::write_png(&self.path, gfx, width, height, self.scale),
+            Format::Pbm => ImageWriter::write_pbm(&self.path, gfx, width, height, self.scale),
+        };
+        if let Err(err) = result {
+            self.error.get_or_insert(err);
+        }
+    }
+}
+
+/** Inserts the frame number before the extension, e.g. `out.png` -> `out-000120.png` */
+fn numbered_path(path: &Path, frame: u64) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("frame");
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+    path.with_file_name(format!("{}-{:06}.{}", stem, frame, extension))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    let file = RomFile::read(&options.rom_path, options.load_address)?;
+
+    let mut builder = Emulator::builder()
+        .quirks(options.quirks)
+        .platform(options.platform)
+        .load_address(options.load_address)
+        .clock_hz(options.hz);
+    if let Some(seed) = options.seed {
+        builder = builder.seed(seed);
+    }
+    let mut emulator = builder.build();
+    let mut frames = options.frames.unwrap_or(600);
+    if let Some(path) = &options.play {
+        let bytes = std::fs::read(path)
+            .map_err(|err| format!("Failed to read '{}': {}", path.display(), err))?;
+        let movie = Movie::from_bytes(&bytes)?;
+        frames = options.frames.unwrap_or(movie.frames());
+        emulator.play_movie(movie, file.rom().to_vec())?;
+    } else if options.record.is_some() {
+        emulator
+            .start_recording(file.rom().to_vec())
+            .map_err(|err| err.to_string())?;
+    } else {
+        match file {
+            RomFile::Rom(rom) => emulator.load_rom(rom),
+            RomFile::Octo(program) => emulator.load_octo_program(program),
+        }
+        .map_err(|err| err.to_string())?;
+    }
+
+    let mut input = ScriptedInput {
+        presses: options.presses,
+        frame: 0,
+    };
+    let mut writer = ImageWriter {
+        path: options.out.clone(),
+        format: options.format,
+        scale: options.scale,
+        error: None,
+    };
+
+    let cycles_per_frame = emulator.cycles_per_frame() as u64;
+    let cycles = options.cycles.unwrap_or(frames * cycles_per_frame);
+
+    let mut fault = None;
+    for cycle in 0..cycles {
+        input.frame = cycle / cycles_per_frame;
+        input.poll(emulator.keypad_mut());
+        if let Err(err) = emulator.cycle() {
+            fault = Some(err);
+            break;
+        }
+
+        if !(cycle + 1).is_multiple_of(cycles_per_frame) {
+            continue;
+        }
+        emulator.tick_timers();
+
+        let frame = (cycle + 1) / cycles_per_frame;
+        if options
+            .every
+            .is_some_and(|every| frame.is_multiple_of(every))
+        {
+            writer.path = numbered_path(&options.out, frame);
+            writer.present(emulator.framebuffer(), emulator.width(), emulator.height());
+        }
+    }
+
+    writer.path = options.out;
+    writer.present(emulator.framebuffer(), emulator.width(), emulator.height());
+
+    if let (Some(path), Some(movie)) = (&options.record, emulator.stop_recording()) {
+        std::fs::write(path, movie.to_bytes())
+            .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))?;
+    }
+
+    if let Some(err) = writer.error {
+        return Err(format!("Failed to write frame: {}", err).into());
+    }
+    match fault {
+        Some(err) => Err(err.to_string().into()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,302 @@
+use chip8_emulator::backend::{AudioSink, InputSource, VideoSink};
+use chip8_emulator::octo::RomFile;
+use chip8_emulator::{Emulator, Keymap, Keypad, Memory, Platform, Preset, Quirks};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{stdout, Stdout, Write};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage: chip8-tty <rom.ch8|source.8o> [--hz <cycles per second>] [--quirks vip|chip48|schip10|schip11|xochip] [--load-address <hex>] [--keymap qwerty|azerty|dvorak|<keymap.json>]";
+const FRAME_DURATION: Duration = Duration::from_micros(1_000_000 / 60);
+/** Terminals without key release events only repeat held keys, so a key counts as held for this long after its last press */
+const KEY_HOLD_DURATION: Duration = Duration::from_millis(150);
+
+struct Options {
+    rom_path: String,
+    hz: u32,
+    quirks: Quirks,
+    platform: Platform,
+    load_address: u16,
+    keymap: Keymap,
+}
+impl Options {
+    fn parse() -> Result<Options, Box<dyn std::error::Error>> {
+        let mut args = std::env::args().skip(1);
+        let mut rom_path = None;
+        let mut hz = 500;
+        let mut quirks = Quirks::default();
+        let mut platform = Platform::default();
+        let mut load_address = Memory::ROM_BEGIN_INDEX;
+        let mut keymap = Keymap::preset("qwerty")?;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--hz" => {
+                    hz = args.next().ok_or("Missing value for --hz")?.parse()?;
+                    if hz == 0 {
+                        return Err("--hz must be greater than 0".into());
+                    }
+                }
+                "--quirks" => {
+                    let name = args.next().ok_or("Missing value for --quirks")?;
+                    let preset = Preset::from_name(&name)?;
+                    quirks = preset.quirks();
+                    platform = preset.platform();
+                }
+                "--load-address" => {
+                    let value = args.next().ok_or("Missing value for --load-address")?;
+                    load_address = u16::from_str_radix(value.trim_start_matches("0x"), 16)?;
+                }
+                "--keymap" => {
+                    let value = args.next().ok_or("Missing value for --keymap")?;
+                    keymap = if value.ends_with(".json") {
+                        Keymap::from_json(&std::fs::read_to_string(&value)?)?
+                    } else {
+                        Keymap::preset(&value)?
+                    };
+                }
+                "-h" | "--help" => return Err(USAGE.into()),
+                _ if rom_path.is_none() => rom_path = Some(arg),
+                _ => return Err(format!("Unexpected argument '{}'\n{}", arg, USAGE).into()),
+            }
+        }
+
+        Ok(Options {
+            rom_path: rom_path.ok_or(USAGE)?,
+            hz,
+            quirks,
+            platform,
+            load_address,
+            keymap,
+        })
+    }
+}
+
+/** Puts the terminal into raw mode on the alternate screen and restores it when dropped, even on panic */
+struct Terminal {
+    stdout: Stdout,
+    enhanced_keyboard: bool,
+}
+impl Terminal {
+    fn init() -> std::io::Result<Terminal> {
+        let mut stdout = stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let enhanced_keyboard = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced_keyboard {
+            execute!(
+                stdout,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+
+        Ok(Terminal {
+            stdout,
+            enhanced_keyboard,
+        })
+    }
+}
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.enhanced_keyboard {
+            let _ = execute!(self.stdout, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.stdout, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/** Draws two pixel rows per terminal line using Unicode half blocks */
+struct HalfBlockScreen {
+    stdout: Stdout,
+    last_frame: Vec<u8>,
+}
+impl HalfBlockScreen {
+    fn init() -> HalfBlockScreen {
+        HalfBlockScreen {
+            stdout: stdout(),
+            last_frame: Vec::new(),
+        }
+    }
+}
+impl VideoSink for HalfBlockScreen {
+    fn present(&mut self, gfx: &[u8], width: usize, height: usize) {
+        // Only redraw when something changed, which keeps SSH sessions responsive
+        if self.last_frame == gfx {
+            return;
+        }
+        // The ROM switched resolution, don't leave the bigger frame behind
+        if self.last_frame.len() != gfx.len() {
+            let _ = queue!(self.stdout, terminal::Clear(terminal::ClearType::All));
+        }
+        self.last_frame = gfx.to_vec();
+
+        let mut frame = String::with_capacity((width + 2) * height / 2 * 3);
+        for row in (0..height).step_by(2) {
+            for col in 0..width {
+                let top = gfx[row * width + col] != 0;
+                let bottom = row + 1 < height && gfx[(row + 1) * width + col] != 0;
+
+                frame.push(match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            frame.push_str("\r\n");
+        }
+
+        let _ = queue!(self.stdout, cursor::MoveTo(0, 0));
+        let _ = self.stdout.write_all(frame.as_bytes());
+        let _ = self.stdout.flush();
+    }
+}
+
+/** Rings the terminal bell whenever the sound timer starts */
+struct Bell {
+    playing: bool,
+}
+impl AudioSink for Bell {
+    fn set_playing(&mut self, playing: bool) {
+        if playing && !self.playing {
+            let mut stdout = stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        self.playing = playing;
+    }
+}
+
+struct TerminalInput {
+    keymap: Keymap,
+    /** When each key was last pressed, or `None` if it is released */
+    pressed_at: [Option<Instant>; 16],
+    enhanced_keyboard: bool,
+    quit: bool,
+}
+impl TerminalInput {
+    fn init(keymap: Keymap, enhanced_keyboard: bool) -> TerminalInput {
+        TerminalInput {
+            keymap,
+            pressed_at: [None; 16],
+            enhanced_keyboard,
+            quit: false,
+        }
+    }
+
+    /** Names terminal keys like the browser's `KeyboardEvent.key`, so keymaps work in both */
+    fn host_key(code: KeyCode) -> Option<String> {
+        let host_key = match code {
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Up => "ArrowUp".to_string(),
+            KeyCode::Down => "ArrowDown".to_string(),
+            KeyCode::Left => "ArrowLeft".to_string(),
+            KeyCode::Right => "ArrowRight".to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            _ => return None,
+        };
+
+        Some(host_key)
+    }
+
+    /** Drains pending terminal events without blocking */
+    fn read_events(&mut self) -> std::io::Result<()> {
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            match key.code {
+                KeyCode::Esc => self.quit = true,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.quit = true
+                }
+                code => {
+                    if let Some(hex) = TerminalInput::host_key(code)
+                        .and_then(|host_key| self.keymap.lookup(&host_key))
+                    {
+                        self.pressed_at[hex as usize] = match key.kind {
+                            KeyEventKind::Release => None,
+                            _ => Some(Instant::now()),
+                        };
+                    }
+                }
+            }
+        }
+
+        if !self.enhanced_keyboard {
+            for pressed_at in self.pressed_at.iter_mut() {
+                if pressed_at.is_some_and(|at| at.elapsed() > KEY_HOLD_DURATION) {
+                    *pressed_at = None;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+impl InputSource for TerminalInput {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        keypad.release_all();
+        for (key, pressed_at) in self.pressed_at.iter().enumerate() {
+            if pressed_at.is_some() {
+                keypad.press(key as u8);
+            }
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match Options::parse() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    let file = RomFile::read(&options.rom_path, options.load_address)?;
+
+    let mut emulator = Emulator::builder()
+        .quirks(options.quirks)
+        .platform(options.platform)
+        .load_address(options.load_address)
+        .clock_hz(options.hz)
+        .build();
+    match file {
+        RomFile::Rom(rom) => emulator.load_rom(rom),
+        RomFile::Octo(program) => emulator.load_octo_program(program),
+    }
+    .map_err(|err| err.to_string())?;
+
+    let terminal = Terminal::init()?;
+    let mut screen = HalfBlockScreen::init();
+    let mut audio = Bell { playing: false };
+    let mut input = TerminalInput::init(options.keymap, terminal.enhanced_keyboard);
+
+    let mut next_frame = Instant::now();
+
+    while !input.quit {
+        input.read_events()?;
+        input.poll(emulator.keypad_mut());
+        emulator.run_frame().map_err(|err| err.to_string())?;
+        audio.set_playing(emulator.is_sound_playing());
+        screen.present(emulator.framebuffer(), emulator.width(), emulator.height());
+
+        next_frame += FRAME_DURATION;
+        let now = Instant::now();
+        if next_frame > now {
+            std::thread::sleep(next_frame - now);
+        } else {
+            next_frame = now;
+        }
+    }
+
+    Ok(())
+}
@@ -1,29 +1,94 @@
-use lazy_static::lazy_static;
-use std::sync::Mutex;
-
-lazy_static! {
-    pub static ref INSTANCE: Mutex<Keypad> = Mutex::new(Keypad::default());
-}
-
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Keypad {
-    current_key: u8,
-    is_key_pressed: bool,
+    /** One bit per hex key, bit 0 is key 0x0 and bit 15 is key 0xF */
+    pressed: u16,
 }
 impl Keypad {
-    pub fn get_current_key(&self) -> u8 {
-        self.current_key
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.pressed & Keypad::mask(key) != 0
+    }
+    pub fn any_pressed(&self) -> bool {
+        self.pressed != 0
+    }
+    /** Lowest pressed key, if any */
+    pub fn first_pressed(&self) -> Option<u8> {
+        if self.any_pressed() {
+            Some(self.pressed.trailing_zeros() as u8)
+        } else {
+            None
+        }
+    }
+
+    pub fn press(&mut self, key: u8) {
+        self.pressed |= Keypad::mask(key);
+    }
+    pub fn release(&mut self, key: u8) {
+        self.pressed &= !Keypad::mask(key);
+    }
+    pub fn release_all(&mut self) {
+        self.pressed = 0;
+    }
+
+    /** Whole keypad as a bit mask, see `pressed` */
+    pub fn state(&self) -> u16 {
+        self.pressed
+    }
+    pub fn set_state(&mut self, pressed: u16) {
+        self.pressed = pressed;
     }
-    pub fn is_key_pressed(&self) -> bool {
-        self.is_key_pressed
+
+    fn mask(key: u8) -> u16 {
+        1 << (key & 0xF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Keypad;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_simultaneous_keys() {
+        // Arrange
+        let mut keypad = Keypad::default();
+
+        // Act
+        keypad.press(0x1);
+        keypad.press(0xC);
+
+        // Assert
+        assert!(keypad.is_pressed(0x1));
+        assert!(keypad.is_pressed(0xC));
+        assert!(!keypad.is_pressed(0x2));
+        assert_eq!(keypad.state(), 0b0001_0000_0000_0010);
     }
 
-    pub fn set_key(&mut self, key: u8) {
-        self.current_key = key;
-        self.is_key_pressed = true;
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_release_one_key() {
+        // Arrange
+        let mut keypad = Keypad::default();
+        keypad.press(0x1);
+        keypad.press(0xC);
+
+        // Act
+        keypad.release(0x1);
+
+        // Assert
+        assert!(!keypad.is_pressed(0x1));
+        assert!(keypad.is_pressed(0xC));
     }
-    pub fn unset_key(&mut self) {
-        self.current_key = 0x0;
-        self.is_key_pressed = false;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_key_0() {
+        // Arrange
+        let mut keypad = Keypad::default();
+
+        // Act
+        keypad.press(0x0);
+
+        // Assert
+        assert!(keypad.is_pressed(0x0));
+        assert!(keypad.any_pressed());
+        assert_eq!(keypad.first_pressed(), Some(0x0));
     }
 }
@@ -1,8 +1,9 @@
-use array_init::array_init;
+use crate::error::EmulatorError;
 
 #[derive(Debug)]
 pub struct Memory {
-    pub data: [u8; 4096],
+    /** Between `SIZE` and `MAX_SIZE` bytes */
+    pub data: Vec<u8>,
 }
 impl Memory {
     pub const FONT_SET: [u8; 80] = [
@@ -23,36 +24,98 @@ impl Memory {
         0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
         0xF0, 0x80, 0xF0, 0x80, 0x80, // F
     ];
+    /** SCHIP's 8x10 digits for FX30, 10 bytes per digit from 0 to F */
+    pub const BIG_FONT_SET: [u8; 160] = [
+        0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+        0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+        0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+        0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+        0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+        0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+        0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+        0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+        0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+        0x18, 0x3C, 0x66, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+        0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC, // B
+        0x3C, 0x7E, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0x7E, 0x3C, // C
+        0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xFF, 0xFF, // E
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xC0, 0xC0, // F
+    ];
     pub const FONT_BEGIN_INDEX: u16 = 0x50;
+    /** Right after `FONT_SET` */
+    pub const BIG_FONT_BEGIN_INDEX: u16 = 0xA0;
     pub const ROM_BEGIN_INDEX: u16 = 0x200;
+    /** Where ETI-660 programs load and start */
+    pub const ETI_660_ROM_BEGIN_INDEX: u16 = 0x600;
+    /** First instruction of programs for the 64x64 hi-res CHIP-8 interpreter, `1260` */
+    pub const HIRES_SIGNATURE: [u8; 2] = [0x12, 0x60];
+    /** Where hi-res programs actually start, past the interpreter patch that `1260` jumps into */
+    pub const HIRES_START_INDEX: u16 = 0x2C0;
+    /** The 4 KB every CHIP-8 interpreter has */
+    pub const SIZE: usize = 0x1000;
+    /** XO-CHIP's 64 KB, all that a 16-bit I can reach */
+    pub const MAX_SIZE: usize = 0x10000;
 
     pub fn init() -> Memory {
+        Memory::with_size(Memory::SIZE)
+    }
+    /** `size` is clamped to `SIZE`..=`MAX_SIZE` */
+    pub fn with_size(size: usize) -> Memory {
         Memory {
-            data: array_init(|_| 0),
+            data: vec![0; size.clamp(Memory::SIZE, Memory::MAX_SIZE)],
         }
     }
 
     pub fn load_fonts(&mut self) {
-        for (i, font) in Memory::FONT_SET.iter().enumerate() {
+        self.load_font_set(&Memory::FONT_SET);
+        self.load_big_font_set(&Memory::BIG_FONT_SET);
+    }
+    /** Loads a custom font set, laid out like `FONT_SET` (5 bytes per hex digit) */
+    pub fn load_font_set(&mut self, font_set: &[u8; 80]) {
+        for (i, font) in font_set.iter().enumerate() {
             self.data[i + Memory::FONT_BEGIN_INDEX as usize] = *font;
         }
     }
-    pub fn load_rom(&mut self, rom: Vec<u8>) {
+    /** Loads a custom big font set, laid out like `BIG_FONT_SET` (10 bytes per hex digit) */
+    pub fn load_big_font_set(&mut self, font_set: &[u8; 160]) {
+        let begin = Memory::BIG_FONT_BEGIN_INDEX as usize;
+        self.data[begin..begin + font_set.len()].copy_from_slice(font_set);
+    }
+    /** Leaves memory untouched if the ROM doesn't fit */
+    pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), EmulatorError> {
+        self.load_rom_at(rom, Memory::ROM_BEGIN_INDEX)
+    }
+    /** Loads the ROM from `address` on, e.g. `ETI_660_ROM_BEGIN_INDEX`. Leaves memory untouched if the ROM doesn't fit */
+    pub fn load_rom_at(&mut self, rom: Vec<u8>, address: u16) -> Result<(), EmulatorError> {
+        let max = self.data.len().saturating_sub(address as usize);
+        if rom.len() > max {
+            return Err(EmulatorError::RomTooLarge {
+                size: rom.len(),
+                max,
+            });
+        }
+
         for (i, value) in rom.iter().enumerate() {
-            self.data[Memory::ROM_BEGIN_INDEX as usize + i] = *value;
+            self.data[address as usize + i] = *value;
         }
+
+        Ok(())
     }
 
     pub fn clear(&mut self) {
-        self.data = array_init(|_| 0);
+        self.data.fill(0);
     }
 }
 
+#[cfg(test)]
 mod tests {
     use super::Memory;
+    use crate::error::EmulatorError;
     use wasm_bindgen_test::wasm_bindgen_test;
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_load_fonts() {
         // Arrange
         let mut memory = Memory::init();
@@ -66,16 +129,21 @@ mod tests {
                 ..(Memory::FONT_BEGIN_INDEX as usize + Memory::FONT_SET.len())],
             Memory::FONT_SET
         );
+        assert_eq!(
+            memory.data[Memory::BIG_FONT_BEGIN_INDEX as usize
+                ..(Memory::BIG_FONT_BEGIN_INDEX as usize + Memory::BIG_FONT_SET.len())],
+            Memory::BIG_FONT_SET
+        );
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_load_rom() {
         // Arrange
         let mut memory = Memory::init();
         let rom = vec![0xAB, 0xCD];
 
         // Act
-        memory.load_rom(rom.clone());
+        memory.load_rom(rom.clone()).unwrap();
 
         // Assert
         assert_eq!(
@@ -84,4 +152,59 @@ mod tests {
             rom
         );
     }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_rom_too_large() {
+        // Arrange
+        let mut memory = Memory::init();
+        let rom = vec![0xAB; 4096 - Memory::ROM_BEGIN_INDEX as usize + 1];
+
+        // Act
+        let result = memory.load_rom(rom);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(EmulatorError::RomTooLarge {
+                size: 3585,
+                max: 3584
+            })
+        );
+        assert!(memory.data.iter().all(|byte| *byte == 0));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_rom_at() {
+        // Arrange
+        let mut memory = Memory::init();
+        let rom = vec![0xAB; 4096 - Memory::ETI_660_ROM_BEGIN_INDEX as usize];
+
+        // Act
+        let result = memory.load_rom_at(rom.clone(), Memory::ETI_660_ROM_BEGIN_INDEX);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(memory.data[0x5FF], 0x00);
+        assert_eq!(memory.data[0x600..], rom);
+        assert_eq!(
+            memory.load_rom_at(vec![0xAB], 0x1000),
+            Err(EmulatorError::RomTooLarge { size: 1, max: 0 })
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_with_size() {
+        // Arrange
+        let mut memory = Memory::with_size(Memory::MAX_SIZE);
+        let rom = vec![0xAB; Memory::MAX_SIZE - Memory::ROM_BEGIN_INDEX as usize];
+
+        // Act
+        let result = memory.load_rom(rom);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(memory.data[0xFFFF], 0xAB);
+        assert_eq!(Memory::with_size(100).data.len(), Memory::SIZE);
+        assert_eq!(Memory::with_size(0x20000).data.len(), Memory::MAX_SIZE);
+    }
 }
@@ -1,16 +1,39 @@
+use super::keypad::Keypad;
 use super::memory::Memory;
-use super::screen::Screen;
+use super::rng::Rng;
+use super::screen::{Resolution, Screen};
+use super::stack::{OverflowPolicy, Stack};
+use crate::error::EmulatorError;
 use crate::opcodes::*;
+use crate::quirks::{Platform, Quirks};
 use array_init::array_init;
 use log::*;
 use wasm_bindgen::prelude::wasm_bindgen;
-use web_sys::{window, HtmlAudioElement};
 
+/** When FX0A considers a key to be entered */
 #[wasm_bindgen]
-#[derive(Debug, PartialEq)]
-pub enum Compatibility {
-    Original,
-    New,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyTrigger {
+    /** As soon as a key is down, like most newer interpreters */
+    Press,
+    /** Once a pressed key is let go again, like the COSMAC VIP */
+    Release,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProcessorState {
+    Running,
+    /** Blocked on FX0A until a key is entered into VX. `key` is the key being held while waiting for its release */
+    WaitingForKey {
+        x: usize,
+        key: Option<u8>,
+    },
+    /** Done with the current frame after a DXYN under the display wait quirk, until `tick_timers` */
+    WaitingForVblank,
+    /** Stopped by 00FD until a ROM or state is loaded */
+    Exited,
+    /** Stopped by a fault until a ROM or state is loaded */
+    Halted(EmulatorError),
 }
 
 #[derive(Debug)]
@@ -22,7 +45,7 @@ pub struct Processor {
     pub i: u16,
 
     /** A stack for 16-bit addresses, which is used to call subroutines/functions and return from them */
-    pub stack: Vec<u16>,
+    pub stack: Stack,
 
     /** Delay timer - 8-bit value which is decremented at a rate of 60 Hz (60 times per second) until it reaches 0 */
     pub delay_timer: u8,
@@ -30,156 +53,311 @@ pub struct Processor {
     /** Sound timer - 8-bit value which functions like the delay timer, but which also gives off a beeping sound as long as it’s not 0 */
     pub sound_timer: u8,
 
-    audio_element: Option<HtmlAudioElement>,
-    timer_subtract: f32,
-
     /** 16 8-bit registers, named V0 to VF. */
     /** VF is also used as a flag register; many instructions will set it to either 1 or 0 based on some rule, for example using it as a carry flag */
     pub v: [u8; 16],
 
-    pub compatibility: Compatibility,
+    /** Address and value of the instruction being executed, for error reports */
+    pub instruction_pc: u16,
+    pub instruction: u16,
+
+    pub quirks: Quirks,
+    pub state: ProcessorState,
+    pub key_trigger: KeyTrigger,
+    pub keypad: Keypad,
     pub memory: Memory,
-    pub gfx: [u8; Screen::WIDTH * Screen::HEIGHT],
+    pub resolution: Resolution,
+    /** Pixels of the current resolution in row-major order, see `framebuffer` */
+    /** Each pixel holds one bit per bitplane, so it's 0 or 1 unless XO-CHIP programs draw to plane 2 */
+    pub gfx: [u8; Screen::MAX_WIDTH * Screen::MAX_HEIGHT],
+    /** Bitplanes that drawing, clearing and scrolling affect, set by XO-CHIP's FN01. Bit 0 is plane 1 */
+    pub planes: u8,
+    /** XO-CHIP's 128 1-bit samples from F002, played instead of the beep once set */
+    pub audio_pattern: Option<[u8; 16]>,
+    /** Playback rate of `audio_pattern` set by FX3A, see `audio_sample_rate` */
+    pub pitch: u8,
+    /** SCHIP's RPL user flags for FX75/FX85, they outlive the ROM */
+    pub rpl: [u8; 16],
+
+    /** Source of random bytes for CXNN */
+    pub rng: Rng,
 }
 impl Processor {
-    /** Initializes with compatibility for original systems */
+    /** 4000 samples per second */
+    pub const DEFAULT_PITCH: u8 = 64;
+
+    /** Initializes like the COSMAC VIP */
     pub fn init() -> Processor {
-        let processor = Processor {
+        Processor {
             pc: Memory::ROM_BEGIN_INDEX,
             i: 0,
-            stack: Vec::new(),
+            stack: Stack::new(Stack::VIP_DEPTH, OverflowPolicy::Fault),
             delay_timer: 0,
             sound_timer: 0,
-            // For tests, which run in Node.js, we don't have a window object
-            audio_element: if window().is_some() {
-                Option::Some(HtmlAudioElement::new().expect("Failed to create audio element!"))
-            } else {
-                Option::None
-            },
-            timer_subtract: 0.0,
             v: array_init(|_| 0),
-            compatibility: Compatibility::Original,
+            instruction_pc: Memory::ROM_BEGIN_INDEX,
+            instruction: 0,
+            quirks: Quirks::cosmac_vip(),
+            state: ProcessorState::Running,
+            key_trigger: KeyTrigger::Release,
+            keypad: Keypad::default(),
             memory: Memory::init(),
+            resolution: Resolution::Low,
             gfx: array_init(|_| 0),
-        };
-        if processor.audio_element.is_some() {
-            processor.audio_element.as_ref().unwrap().set_src("data:audio/wav;base64,//uQRAAAAWMSLwUIYAAsYkXgoQwAEaYLWfkWgAI0wWs/ItAAAGDgYtAgAyN+QWaAAihwMWm4G8QQRDiMcCBcH3Cc+CDv/7xA4Tvh9Rz/y8QADBwMWgQAZG/ILNAARQ4GLTcDeIIIhxGOBAuD7hOfBB3/94gcJ3w+o5/5eIAIAAAVwWgQAVQ2ORaIQwEMAJiDg95G4nQL7mQVWI6GwRcfsZAcsKkJvxgxEjzFUgfHoSQ9Qq7KNwqHwuB13MA4a1q/DmBrHgPcmjiGoh//EwC5nGPEmS4RcfkVKOhJf+WOgoxJclFz3kgn//dBA+ya1GhurNn8zb//9NNutNuhz31f////9vt///z+IdAEAAAK4LQIAKobHItEIYCGAExBwe8jcToF9zIKrEdDYIuP2MgOWFSE34wYiR5iqQPj0JIeoVdlG4VD4XA67mAcNa1fhzA1jwHuTRxDUQ//iYBczjHiTJcIuPyKlHQkv/LHQUYkuSi57yQT//uggfZNajQ3Vmz+Zt//+mm3Wm3Q576v////+32///5/EOgAAADVghQAAAAA//uQZAUAB1WI0PZugAAAAAoQwAAAEk3nRd2qAAAAACiDgAAAAAAABCqEEQRLCgwpBGMlJkIz8jKhGvj4k6jzRnqasNKIeoh5gI7BJaC1A1AoNBjJgbyApVS4IDlZgDU5WUAxEKDNmmALHzZp0Fkz1FMTmGFl1FMEyodIavcCAUHDWrKAIA4aa2oCgILEBupZgHvAhEBcZ6joQBxS76AgccrFlczBvKLC0QI2cBoCFvfTDAo7eoOQInqDPBtvrDEZBNYN5xwNwxQRfw8ZQ5wQVLvO8OYU+mHvFLlDh05Mdg7BT6YrRPpCBznMB2r//xKJjyyOh+cImr2/4doscwD6neZjuZR4AgAABYAAAABy1xcdQtxYBYYZdifkUDgzzXaXn98Z0oi9ILU5mBjFANmRwlVJ3/6jYDAmxaiDG3/6xjQQCCKkRb/6kg/wW+kSJ5//rLobkLSiKmqP/0ikJuDaSaSf/6JiLYLEYnW/+kXg1WRVJL/9EmQ1YZIsv/6Qzwy5qk7/+tEU0nkls3/zIUMPKNX/6yZLf+kFgAfgGyLFAUwY//uQZAUABcd5UiNPVXAAAApAAAAAE0VZQKw9ISAAACgAAAAAVQIygIElVrFkBS+Jhi+EAuu+lKAkYUEIsmEAEoMeDmCETMvfSHTGkF5RWH7kz/ESHWPAq/kcCRhqBtMdokPdM7vil7RG98A2sc7zO6ZvTdM7pmOUAZTnJW+NXxqmd41dqJ6mLTXxrPpnV8avaIf5SvL7pndPvPpndJR9Kuu8fePvuiuhorgWjp7Mf/PRjxcFCPDkW31srioCExivv9lcwKEaHsf/7ow2Fl1T/9RkXgEhYElAoCLFtMArxwivDJJ+bR1HTKJdlEoTELCIqgEwVGSQ+hIm0NbK8WXcTEI0UPoa2NbG4y2K00JEWbZavJXkYaqo9CRHS55FcZTjKEk3NKoCYUnSQ0rWxrZbFKbKIhOKPZe1cJKzZSaQrIyULHDZmV5K4xySsDRKWOruanGtjLJXFEmwaIbDLX0hIPBUQPVFVkQkDoUNfSoDgQGKPekoxeGzA4DUvnn4bxzcZrtJyipKfPNy5w+9lnXwgqsiyHNeSVpemw4bWb9psYeq//uQZBoABQt4yMVxYAIAAAkQoAAAHvYpL5m6AAgAACXDAAAAD59jblTirQe9upFsmZbpMudy7Lz1X1DYsxOOSWpfPqNX2WqktK0DMvuGwlbNj44TleLPQ+Gsfb+GOWOKJoIrWb3cIMeeON6lz2umTqMXV8Mj30yWPpjoSa9ujK8SyeJP5y5mOW1D6hvLepeveEAEDo0mgCRClOEgANv3B9a6fikgUSu/DmAMATrGx7nng5p5iimPNZsfQLYB2sDLIkzRKZOHGAaUyDcpFBSLG9MCQALgAIgQs2YunOszLSAyQYPVC2YdGGeHD2dTdJk1pAHGAWDjnkcLKFymS3RQZTInzySoBwMG0QueC3gMsCEYxUqlrcxK6k1LQQcsmyYeQPdC2YfuGPASCBkcVMQQqpVJshui1tkXQJQV0OXGAZMXSOEEBRirXbVRQW7ugq7IM7rPWSZyDlM3IuNEkxzCOJ0ny2ThNkyRai1b6ev//3dzNGzNb//4uAvHT5sURcZCFcuKLhOFs8mLAAEAt4UWAAIABAAAAAB4qbHo0tIjVkUU//uQZAwABfSFz3ZqQAAAAAngwAAAE1HjMp2qAAAAACZDgAAAD5UkTE1UgZEUExqYynN1qZvqIOREEFmBcJQkwdxiFtw0qEOkGYfRDifBui9MQg4QAHAqWtAWHoCxu1Yf4VfWLPIM2mHDFsbQEVGwyqQoQcwnfHeIkNt9YnkiaS1oizycqJrx4KOQjahZxWbcZgztj2c49nKmkId44S71j0c8eV9yDK6uPRzx5X18eDvjvQ6yKo9ZSS6l//8elePK/Lf//IInrOF/FvDoADYAGBMGb7FtErm5MXMlmPAJQVgWta7Zx2go+8xJ0UiCb8LHHdftWyLJE0QIAIsI+UbXu67dZMjmgDGCGl1H+vpF4NSDckSIkk7Vd+sxEhBQMRU8j/12UIRhzSaUdQ+rQU5kGeFxm+hb1oh6pWWmv3uvmReDl0UnvtapVaIzo1jZbf/pD6ElLqSX+rUmOQNpJFa/r+sa4e/pBlAABoAAAAA3CUgShLdGIxsY7AUABPRrgCABdDuQ5GC7DqPQCgbbJUAoRSUj+NIEig0YfyWUho1VBBBA//uQZB4ABZx5zfMakeAAAAmwAAAAF5F3P0w9GtAAACfAAAAAwLhMDmAYWMgVEG1U0FIGCBgXBXAtfMH10000EEEEEECUBYln03TTTdNBDZopopYvrTTdNa325mImNg3TTPV9q3pmY0xoO6bv3r00y+IDGid/9aaaZTGMuj9mpu9Mpio1dXrr5HERTZSmqU36A3CumzN/9Robv/Xx4v9ijkSRSNLQhAWumap82WRSBUqXStV/YcS+XVLnSS+WLDroqArFkMEsAS+eWmrUzrO0oEmE40RlMZ5+ODIkAyKAGUwZ3mVKmcamcJnMW26MRPgUw6j+LkhyHGVGYjSUUKNpuJUQoOIAyDvEyG8S5yfK6dhZc0Tx1KI/gviKL6qvvFs1+bWtaz58uUNnryq6kt5RzOCkPWlVqVX2a/EEBUdU1KrXLf40GoiiFXK///qpoiDXrOgqDR38JB0bw7SoL+ZB9o1RCkQjQ2CBYZKd/+VJxZRRZlqSkKiws0WFxUyCwsKiMy7hUVFhIaCrNQsKkTIsLivwKKigsj8XYlwt/WKi2N4d//uQRCSAAjURNIHpMZBGYiaQPSYyAAABLAAAAAAAACWAAAAApUF/Mg+0aohSIRobBAsMlO//Kk4soosy1JSFRYWaLC4qZBYWFRGZdwqKiwkNBVmoWFSJkWFxX4FFRQWR+LsS4W/rFRb/////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////VEFHAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAU291bmRib3kuZGUAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAMjAwNGh0dHA6Ly93d3cuc291bmRib3kuZGUAAAAAAAAAACU=");
+            planes: 1,
+            audio_pattern: None,
+            pitch: Processor::DEFAULT_PITCH,
+            rpl: [0; 16],
+            rng: Rng::from_entropy(),
         }
-
-        processor
     }
-    /** Initializes with compatibility for newer systems */
-    pub fn init_newer() -> Processor {
+    /** Initializes with the given quirks on the default `Platform` */
+    pub fn init_quirks(quirks: Quirks) -> Processor {
+        Processor::init_platform(quirks, Platform::default())
+    }
+    /** Initializes with the given quirks on the given machine, faulting on stack overflow */
+    pub fn init_platform(quirks: Quirks, platform: Platform) -> Processor {
+        info!("Initializing processor with {:?} on {:?}", quirks, platform);
+
         let mut processor = Processor::init();
-        processor.compatibility = Compatibility::New;
+        processor.quirks = quirks;
+        processor.key_trigger = platform.key_trigger;
+        processor.stack = Stack::new(platform.stack_depth, OverflowPolicy::Fault);
+        processor.memory = Memory::with_size(platform.memory_size);
 
         processor
     }
-    /** Initializes with specified compatibility */
-    pub fn init_compat(compatibility: Compatibility) -> Processor {
-        match compatibility {
-            Compatibility::Original => {
-                info!("Initializing processor with compatibility for original systems");
-                Processor::init()
-            }
-            Compatibility::New => {
-                info!("Initializing processor with compatibility for newer systems");
-                Processor::init_newer()
+
+    /** Runs one instruction. On a fault the processor halts and keeps returning the fault */
+    pub fn cycle(&mut self) -> Result<(), EmulatorError> {
+        match self.state {
+            ProcessorState::Halted(err) => return Err(err),
+            ProcessorState::WaitingForKey { .. } => {
+                self.update_key_wait();
+                return Ok(());
             }
+            ProcessorState::WaitingForVblank | ProcessorState::Exited => return Ok(()),
+            ProcessorState::Running => {}
         }
-    }
 
-    pub fn cycle(&mut self) {
         debug!("==========================");
 
-        let instruction = self.fetch();
-        self.pc += 2;
-        debug!("Instruction: {:#06X}, PC: {:#06X}", instruction, self.pc);
+        let result = self.fetch().and_then(|instruction| {
+            self.pc = self.pc.wrapping_add(2);
+            debug!("Instruction: {:#06X}, PC: {:#06X}", instruction, self.pc);
 
-        let (first, rest) = self.decode(instruction);
-
-        self.execute(first, rest).unwrap_or_else(|err| {
-            warn!("{}", err);
+            let (first, rest) = Processor::decode(instruction);
+            self.execute(first, rest)
         });
+        if let Err(err) = result {
+            warn!("{}", err);
+            self.state = ProcessorState::Halted(err);
+        }
 
-        self.update_timers();
+        result
     }
-    fn update_timers(&mut self) {
-        if self.audio_element.is_none() {
-            return;
+    /** Runs one 60 Hz frame: a batch of instructions followed by a timer tick */
+    /** Stops at a fault without ticking the timers */
+    pub fn run_frame(&mut self, instructions_per_frame: u32) -> Result<(), EmulatorError> {
+        for _ in 0..instructions_per_frame {
+            self.cycle()?;
         }
+        self.tick_timers();
 
-        // We can't do this in a separate thread so we do it this way
-        self.timer_subtract += 1.0 / 10.0;
+        Ok(())
+    }
+    /** Decrements the delay and sound timers, the host must call this at 60 Hz */
+    /** This is the vertical blank too, so a processor waiting for it resumes */
+    pub fn tick_timers(&mut self) {
+        if self.state == ProcessorState::WaitingForVblank {
+            self.state = ProcessorState::Running;
+        }
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
 
-        if self.timer_subtract >= 1.0 {
-            if self.delay_timer > 0 {
-                self.delay_timer -= 1;
-            }
-            #[allow(unused_must_use)]
-            if self.sound_timer > 0 {
-                self.audio_element
-                    .as_ref()
-                    .unwrap()
-                    .play()
-                    .expect("Failed to play audio!");
-                self.sound_timer -= 1;
+    pub fn is_waiting_for_key(&self) -> bool {
+        matches!(self.state, ProcessorState::WaitingForKey { .. })
+    }
+    /** Finishes FX0A once a key has been entered according to `key_trigger` */
+    pub fn update_key_wait(&mut self) {
+        let ProcessorState::WaitingForKey { x, key } = self.state else {
+            return;
+        };
+
+        let entered = match (self.key_trigger, key) {
+            (KeyTrigger::Press, _) => self.keypad.first_pressed(),
+            (KeyTrigger::Release, None) => {
+                self.state = ProcessorState::WaitingForKey {
+                    x,
+                    key: self.keypad.first_pressed(),
+                };
+                None
             }
+            (KeyTrigger::Release, Some(key)) => (!self.keypad.is_pressed(key)).then_some(key),
+        };
+
+        if let Some(key) = entered {
+            self.v[x] = key;
+            self.state = ProcessorState::Running;
+            debug!("Key {:X} entered into V{:X}", key, x);
+        }
+    }
 
-            self.timer_subtract = 0.0;
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        self.state == ProcessorState::WaitingForVblank
+    }
+
+    pub fn is_exited(&self) -> bool {
+        self.state == ProcessorState::Exited
+    }
+
+    pub fn width(&self) -> usize {
+        self.resolution.width()
+    }
+    pub fn height(&self) -> usize {
+        self.resolution.height()
+    }
+    /** The part of `gfx` in use, `width() * height()` pixels */
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.gfx[..self.width() * self.height()]
+    }
+    /** Switches resolution and clears the screen */
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        self.gfx = array_init(|_| 0);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        matches!(self.state, ProcessorState::Halted(_))
+    }
+    /** The fault that halted the processor, if any */
+    pub fn fault(&self) -> Option<EmulatorError> {
+        match self.state {
+            ProcessorState::Halted(err) => Some(err),
+            _ => None,
         }
     }
 
-    fn fetch(&self) -> u16 {
+    /** Samples per second to play `audio_pattern` at */
+    pub fn audio_sample_rate(&self) -> f64 {
+        4000.0 * 2_f64.powf((self.pitch as f64 - 64.0) / 48.0)
+    }
+    /** Whether the beep should currently be audible */
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    fn fetch(&mut self) -> Result<u16, EmulatorError> {
+        self.instruction_pc = self.pc;
+        self.instruction = 0;
+        if self.pc as usize + 1 >= self.memory.data.len() {
+            return Err(EmulatorError::MemoryOutOfBounds {
+                pc: self.pc,
+                opcode: 0,
+                address: self.memory.data.len(),
+            });
+        }
+
         let first_half = self.memory.data[self.pc as usize] as u16;
         let second_half = self.memory.data[self.pc as usize + 1] as u16;
 
-        (first_half) << 0x8 | second_half
+        Ok((first_half) << 0x8 | second_half)
     }
-    fn decode(&self, instruction: u16) -> (u16, u16) {
+    /** Splits an instruction into its first nibble, which picks the opcode group, and the 12 bits after it */
+    pub fn decode(instruction: u16) -> (u16, u16) {
         let first = (instruction & 0xF000) >> 0xC;
         let rest = instruction & 0x0FFF;
 
         (first, rest)
     }
-    pub fn execute(&mut self, first: u16, rest: u16) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn execute(&mut self, first: u16, rest: u16) -> Result<(), EmulatorError> {
+        self.instruction = first << 0xC | rest;
         let mut not_found = false;
 
         match first {
             0x0 => match rest {
                 0x0E0 => {
-                    OpCode00E0::execute(self, &[]);
+                    OpCode00E0::execute(self, &[])?;
 
                     debug!("Clear screen");
                 }
+                0x230 if self.resolution == Resolution::TwoPage => {
+                    OpCode00E0::execute(self, &[])?;
+
+                    debug!("Clear the 64x64 screen");
+                }
                 0x0EE => {
-                    OpCode00EE::execute(self, &[]);
+                    OpCode00EE::execute(self, &[])?;
 
                     debug!("Return from subroutine");
                 }
+                0x0C0..=0x0CF => {
+                    let n = rest & 0x00F;
+                    OpCode00CN::execute(self, &[n])?;
+
+                    debug!("Scroll down {} rows", n);
+                }
+                0x0D0..=0x0DF => {
+                    let n = rest & 0x00F;
+                    OpCode00DN::execute(self, &[n])?;
+
+                    debug!("Scroll up {} rows", n);
+                }
+                0x0FB => {
+                    OpCode00FB::execute(self, &[])?;
+
+                    debug!("Scroll right 4 pixels");
+                }
+                0x0FC => {
+                    OpCode00FC::execute(self, &[])?;
+
+                    debug!("Scroll left 4 pixels");
+                }
+                0x0FD => {
+                    OpCode00FD::execute(self, &[])?;
+
+                    debug!("Exit");
+                }
+                0x0FE => {
+                    OpCode00FE::execute(self, &[])?;
+
+                    debug!("Switch to low resolution");
+                }
+                0x0FF => {
+                    OpCode00FF::execute(self, &[])?;
+
+                    debug!("Switch to high resolution");
+                }
                 _ => {
                     not_found = true;
                 }
             },
             0x1 => {
                 let nnn = rest;
-                OpCode1NNN::execute(self, &[nnn]);
+                OpCode1NNN::execute(self, &[nnn])?;
 
                 debug!("Jump to {:#06X} -> {:#06X}", rest, self.pc);
             }
             0x2 => {
                 let nnn = rest;
-                OpCode2NNN::execute(self, &[nnn]);
+                OpCode2NNN::execute(self, &[nnn])?;
 
                 debug!(
-                    "Call subroutine at {:#06X} -> stack[0]={:#06X}",
-                    rest, self.stack[0]
+                    "Call subroutine at {:#06X} -> stack[{}]={:#06X}",
+                    rest,
+                    self.stack.len() - 1,
+                    self.stack.top().unwrap_or_default()
                 );
             }
             0x3 => {
                 let x = (rest & 0xF00) >> 8;
                 let nn = rest & 0x0FF;
-                OpCode3XNN::execute(self, &[x, nn]);
+                OpCode3XNN::execute(self, &[x, nn])?;
 
                 debug!(
                     "Skip next instruction if V{:X} ({:#06X}) == {:#06X}",
@@ -189,34 +367,59 @@ impl Processor {
             0x4 => {
                 let x = (rest & 0xF00) >> 8;
                 let nn = rest & 0x0FF;
-                OpCode4XNN::execute(self, &[x, nn]);
+                OpCode4XNN::execute(self, &[x, nn])?;
 
                 debug!(
                     "Skip next instruction if V{:X} ({:#06X}) != {:#06X}",
                     x, self.v[x as usize], nn
                 );
             }
-            0x5 => {
-                let x = (rest & 0xF00) >> 8;
-                let y = (rest & 0x0F0) >> 4;
-                OpCode5XY0::execute(self, &[x, y]);
+            0x5 => match rest & 0x00F {
+                0x0 => {
+                    let x = (rest & 0xF00) >> 8;
+                    let y = (rest & 0x0F0) >> 4;
+                    OpCode5XY0::execute(self, &[x, y])?;
 
-                debug!(
-                    "Skip next instruction if V{:X} ({:#06X}) == V{:X} ({:#06X})",
-                    x, self.v[x as usize], y, self.v[y as usize]
-                );
-            }
+                    debug!(
+                        "Skip next instruction if V{:X} ({:#06X}) == V{:X} ({:#06X})",
+                        x, self.v[x as usize], y, self.v[y as usize]
+                    );
+                }
+                0x2 => {
+                    let x = (rest & 0xF00) >> 8;
+                    let y = (rest & 0x0F0) >> 4;
+                    OpCode5XY2::execute(self, &[x, y])?;
+
+                    debug!(
+                        "Store registers V{:X} through V{:X} in memory starting at location I ({:#06X})",
+                        x, y, self.i
+                    );
+                }
+                0x3 => {
+                    let x = (rest & 0xF00) >> 8;
+                    let y = (rest & 0x0F0) >> 4;
+                    OpCode5XY3::execute(self, &[x, y])?;
+
+                    debug!(
+                        "Read registers V{:X} through V{:X} from memory starting at location I ({:#06X})",
+                        x, y, self.i
+                    );
+                }
+                _ => {
+                    not_found = true;
+                }
+            },
             0x6 => {
                 let x = (rest & 0xF00) >> 8;
                 let nn = rest & 0x0FF;
-                OpCode6XNN::execute(self, &[x, nn]);
+                OpCode6XNN::execute(self, &[x, nn])?;
 
                 debug!("Set V{:X} to {:#06X} -> {:#06X}", x, nn, self.v[x as usize]);
             }
             0x7 => {
                 let x = (rest & 0xF00) >> 8;
                 let nn = rest & 0x0FF;
-                OpCode7XNN::execute(self, &[x, nn]);
+                OpCode7XNN::execute(self, &[x, nn])?;
 
                 debug!("Add {:#06X} to V{:X} -> {:#06X}", nn, x, self.v[x as usize]);
             }
@@ -224,7 +427,7 @@ impl Processor {
                 0x0 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY0::execute(self, &[x, y]);
+                    OpCode8XY0::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} ({:#06X}) -> {:#06X}",
@@ -234,7 +437,7 @@ impl Processor {
                 0x1 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY1::execute(self, &[x, y]);
+                    OpCode8XY1::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} | V{:X} -> {:#06X}",
@@ -244,7 +447,7 @@ impl Processor {
                 0x2 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY2::execute(self, &[x, y]);
+                    OpCode8XY2::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} & V{:X} -> {:#06X}",
@@ -254,7 +457,7 @@ impl Processor {
                 0x3 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY3::execute(self, &[x, y]);
+                    OpCode8XY3::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} ^ V{:X} -> {:#06X}",
@@ -264,7 +467,7 @@ impl Processor {
                 0x4 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY4::execute(self, &[x, y]);
+                    OpCode8XY4::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} + V{:X} -> {:#06X}",
@@ -274,7 +477,7 @@ impl Processor {
                 0x5 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY5::execute(self, &[x, y]);
+                    OpCode8XY5::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} - V{:X} -> {:#06X}",
@@ -284,7 +487,7 @@ impl Processor {
                 0x6 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY6::execute(self, &[x, y]);
+                    OpCode8XY6::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} >> 1 -> {:#06X}",
@@ -294,7 +497,7 @@ impl Processor {
                 0x7 => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XY7::execute(self, &[x, y]);
+                    OpCode8XY7::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} - V{:X} -> {:#06X}",
@@ -304,7 +507,7 @@ impl Processor {
                 0xE => {
                     let x = (rest & 0xF00) >> 8;
                     let y = (rest & 0x0F0) >> 4;
-                    OpCode8XYE::execute(self, &[x, y]);
+                    OpCode8XYE::execute(self, &[x, y])?;
 
                     debug!(
                         "Set V{:X} to V{:X} << 1 -> {:#06X}",
@@ -318,7 +521,7 @@ impl Processor {
             0x9 => {
                 let x = (rest & 0xF00) >> 8;
                 let y = (rest & 0x0F0) >> 4;
-                OpCode9XY0::execute(self, &[x, y]);
+                OpCode9XY0::execute(self, &[x, y])?;
 
                 debug!(
                     "Skip next instruction if V{:X} ({:#06X}) != V{:X} ({:#06X})",
@@ -327,22 +530,22 @@ impl Processor {
             }
             0xA => {
                 let nnn = rest;
-                OpCodeANNN::execute(self, &[nnn]);
+                OpCodeANNN::execute(self, &[nnn])?;
 
                 debug!("Set I to {:#06X} -> {:#06X}", rest, self.i);
             }
             0xB => {
                 let nnn = rest;
-                if self.compatibility == Compatibility::Original {
-                    OpCodeBNNN::execute(self, &[nnn]);
+                if !self.quirks.jump {
+                    OpCodeBNNN::execute(self, &[nnn])?;
 
                     debug!(
                         "Jump to {:#06X} + V0 ({:#06X}) -> {:#06X}",
                         nnn, self.v[0], self.pc
                     );
-                } else if self.compatibility == Compatibility::New {
+                } else {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeBXNN::execute(self, &[x, nnn]);
+                    OpCodeBXNN::execute(self, &[x, nnn])?;
 
                     debug!(
                         "Jump to {:#06X} + V{:X} ({:#06X}) -> {:#06X}",
@@ -353,7 +556,7 @@ impl Processor {
             0xC => {
                 let x = (rest & 0xF00) >> 8;
                 let nn = rest & 0x0FF;
-                OpCodeCXNN::execute(self, &[x, nn]);
+                OpCodeCXNN::execute(self, &[x, nn])?;
 
                 debug!(
                     "Set V{:X} to random byte & {:#06X} -> {:#06X}",
@@ -364,17 +567,19 @@ impl Processor {
                 let x = (rest & 0xF00) >> 8;
                 let y = (rest & 0x0F0) >> 4;
                 let n = rest & 0x00F;
-                OpCodeDXYN::execute(self, &[x, y, n]);
+                OpCodeDXYN::execute(self, &[x, y, n])?;
 
                 debug!(
                     "Draw sprite at {}:{} with height {}",
-                    self.v[x as usize], self.v[y as usize], n
+                    self.v[x as usize],
+                    self.v[y as usize],
+                    if n == 0 { 16 } else { n }
                 );
             }
             0xE => match rest & 0x0FF {
                 0x9E => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeEX9E::execute(self, &[x]);
+                    OpCodeEX9E::execute(self, &[x])?;
 
                     debug!(
                         "Skip next instruction if key {:#06X} is pressed",
@@ -383,7 +588,7 @@ impl Processor {
                 }
                 0xA1 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeEXA1::execute(self, &[x]);
+                    OpCodeEXA1::execute(self, &[x])?;
 
                     debug!(
                         "Skip next instruction if key {:#06X} is not pressed",
@@ -395,9 +600,25 @@ impl Processor {
                 }
             },
             0xF => match rest & 0x0FF {
+                0x00 if rest == 0x000 => {
+                    OpCodeF000::execute(self, &[])?;
+
+                    debug!("Set I to long address -> {:#06X}", self.i);
+                }
+                0x01 => {
+                    let n = (rest & 0xF00) >> 8;
+                    OpCodeFN01::execute(self, &[n])?;
+
+                    debug!("Select bitplanes {:#04b}", self.planes);
+                }
+                0x02 if rest == 0x002 => {
+                    OpCodeF002::execute(self, &[])?;
+
+                    debug!("Load audio pattern from memory at I ({:#06X})", self.i);
+                }
                 0x07 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX07::execute(self, &[x]);
+                    OpCodeFX07::execute(self, &[x])?;
 
                     debug!(
                         "Set V{:X} to delay timer ({:#06X}) -> {:#06X}",
@@ -406,13 +627,13 @@ impl Processor {
                 }
                 0x0A => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX0A::execute(self, &[x]);
+                    OpCodeFX0A::execute(self, &[x])?;
 
                     debug!("Wait for keypress and store in V{:X}", x);
                 }
                 0x15 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX15::execute(self, &[x]);
+                    OpCodeFX15::execute(self, &[x])?;
 
                     debug!(
                         "Set delay timer to V{:X} ({:#06X}) -> {:#06X}",
@@ -421,7 +642,7 @@ impl Processor {
                 }
                 0x18 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX18::execute(self, &[x]);
+                    OpCodeFX18::execute(self, &[x])?;
 
                     debug!(
                         "Set sound timer to V{:X} ({:#06X}) -> {:#06X}",
@@ -430,16 +651,25 @@ impl Processor {
                 }
                 0x29 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX29::execute(self, &[x]);
+                    OpCodeFX29::execute(self, &[x])?;
 
                     debug!(
                         "Set I to location of sprite for digit V{:X} ({:#06X}) -> {:#06X}",
                         x, self.v[x as usize], self.i
                     );
                 }
+                0x30 => {
+                    let x = (rest & 0xF00) >> 8;
+                    OpCodeFX30::execute(self, &[x])?;
+
+                    debug!(
+                        "Set I to location of big sprite for digit V{:X} ({:#06X}) -> {:#06X}",
+                        x, self.v[x as usize], self.i
+                    );
+                }
                 0x33 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX33::execute(self, &[x]);
+                    OpCodeFX33::execute(self, &[x])?;
 
                     debug!(
                         "Store BCD representation of V{:X} ({:#06X}) in memory at I ({:#06X})",
@@ -448,16 +678,22 @@ impl Processor {
                 }
                 0x1E => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX1E::execute(self, &[x]);
+                    OpCodeFX1E::execute(self, &[x])?;
 
                     debug!(
                         "Set I to I + V{:X} ({:#06X}) -> {:#06X}",
                         x, self.v[x as usize], self.i
                     );
                 }
+                0x3A => {
+                    let x = (rest & 0xF00) >> 8;
+                    OpCodeFX3A::execute(self, &[x])?;
+
+                    debug!("Set pitch to V{:X} -> {}", x, self.pitch);
+                }
                 0x55 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX55::execute(self, &[x]);
+                    OpCodeFX55::execute(self, &[x])?;
 
                     debug!(
 						"Store registers V0 through V{:X} in memory starting at location I ({:#06X})",
@@ -466,13 +702,25 @@ impl Processor {
                 }
                 0x65 => {
                     let x = (rest & 0xF00) >> 8;
-                    OpCodeFX65::execute(self, &[x]);
+                    OpCodeFX65::execute(self, &[x])?;
 
                     debug!(
 						"Read registers V0 through V{:X} from memory starting at location I ({:#06X})",
 						x, self.i
 					);
                 }
+                0x75 => {
+                    let x = (rest & 0xF00) >> 8;
+                    OpCodeFX75::execute(self, &[x])?;
+
+                    debug!("Store registers V0 through V{:X} in the RPL flags", x);
+                }
+                0x85 => {
+                    let x = (rest & 0xF00) >> 8;
+                    OpCodeFX85::execute(self, &[x])?;
+
+                    debug!("Read registers V0 through V{:X} from the RPL flags", x);
+                }
                 _ => {
                     not_found = true;
                 }
@@ -484,7 +732,10 @@ impl Processor {
         }
 
         if not_found {
-            Err(format!("Opcode {:#06X} not recognized!", first << 0xC | rest).into())
+            Err(EmulatorError::UnknownOpcode {
+                pc: self.instruction_pc,
+                opcode: self.instruction,
+            })
         } else {
             Ok(())
         }
@@ -493,14 +744,16 @@ impl Processor {
 
 #[cfg(test)]
 mod tests {
-    use super::Processor;
-    use array_init::array_init;
+    use super::{Processor, ProcessorState};
+    use crate::components::memory::Memory;
+    use crate::error::EmulatorError;
+    use crate::quirks::Quirks;
     use wasm_bindgen_test::wasm_bindgen_test;
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_fetch() {
         // Arrange
-        let mut memory: [u8; 4096] = array_init(|_| 0);
+        let mut memory = vec![0; 4096];
         memory[0] = 0xAB;
         memory[1] = 0xCD;
         let pc: u16 = 0x0;
@@ -510,28 +763,110 @@ mod tests {
         processor.pc = pc;
 
         // Act
-        let result = Processor::fetch(&processor);
+        let result = Processor::fetch(&mut processor).unwrap();
 
         // Assert
         let expected = 0xABCD;
         assert_eq!(result, expected, "{:#06X} =/= {:#06X}", result, expected);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_cycle_wraps_pc() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.memory = Memory::with_size(Memory::MAX_SIZE);
+        processor.memory.data[0xFFFE..].copy_from_slice(&[0x60, 0x2A]);
+        processor.pc = 0xFFFE;
+
+        // Act
+        processor.cycle().unwrap();
+
+        // Assert
+        assert_eq!(processor.pc, 0);
+        assert_eq!(processor.v[0], 0x2A);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_decode() {
         // Arrange
         let instruction: u16 = 0xABCD;
-        let processor = Processor::init();
 
         // Act
-        let result = processor.decode(instruction);
+        let result = Processor::decode(instruction);
 
         // Assert
         let expected: (u16, u16) = (0xA, 0xBCD);
         assert_eq!(result, expected, "{:#06X?} =/= {:#06X?}", result, expected);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_tick_timers() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.delay_timer = 0x2;
+        processor.sound_timer = 0x1;
+
+        // Act
+        processor.tick_timers();
+        processor.tick_timers();
+
+        // Assert
+        assert_eq!(processor.delay_timer, 0x0);
+        assert_eq!(processor.sound_timer, 0x0);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_run_frame() {
+        // Arrange
+        let mut fast = Processor::init();
+        let mut slow = Processor::init();
+        for processor in [&mut fast, &mut slow] {
+            processor.delay_timer = 0x10;
+            // 1200: jump to self
+            processor.memory.data[0x200] = 0x12;
+            processor.memory.data[0x201] = 0x00;
+        }
+
+        // Act
+        fast.run_frame(100).unwrap();
+        slow.run_frame(1).unwrap();
+
+        // Assert
+        assert_eq!(fast.delay_timer, 0xF, "Timers should tick once per frame");
+        assert_eq!(slow.delay_timer, 0xF, "Timers should tick once per frame");
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_display_wait() {
+        // Arrange
+        let mut vip = Processor::init();
+        let mut schip = Processor::init_quirks(Quirks::schip_1_1());
+        for processor in [&mut vip, &mut schip] {
+            // 6001: V0 = 1, D001: draw, 7001: V0 += 1, D001: draw, 1208: jump to self
+            let rom = [0x60, 0x01, 0xD0, 0x01, 0x70, 0x01, 0xD0, 0x01, 0x12, 0x08];
+            processor.memory.data[0x200..0x20A].copy_from_slice(&rom);
+        }
+
+        // Act
+        vip.run_frame(10).unwrap();
+        schip.run_frame(10).unwrap();
+        let first_frame_pc = vip.pc;
+        vip.cycle().unwrap();
+        vip.cycle().unwrap();
+        let waiting = vip.is_waiting_for_vblank();
+        vip.cycle().unwrap();
+
+        // Assert
+        assert_eq!(first_frame_pc, 0x204, "Only one draw per frame");
+        assert!(waiting);
+        assert_eq!(vip.pc, 0x208, "Nothing runs until the next frame");
+        assert_eq!(vip.v[0], 2);
+        assert_eq!(schip.pc, 0x208);
+        vip.tick_timers();
+        assert_eq!(vip.state, ProcessorState::Running);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_execute_normal() {
         // Arrange
         let first = 0x0;
@@ -544,7 +879,7 @@ mod tests {
         // Assert
         assert!(result.is_ok());
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_execute_not_implemented() {
         // Arrange
         let first = 0xF;
@@ -557,4 +892,64 @@ mod tests {
         // Assert
         assert!(result.is_err());
     }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_cycle_halts_on_fault() {
+        // Arrange
+        let mut processor = Processor::init();
+        // 00EE: return with an empty stack
+        processor.memory.data[0x200] = 0x00;
+        processor.memory.data[0x201] = 0xEE;
+
+        // Act
+        let first = processor.cycle();
+        let second = processor.cycle();
+
+        // Assert
+        let fault = EmulatorError::StackUnderflow {
+            pc: 0x200,
+            opcode: 0x00EE,
+        };
+        assert_eq!(first, Err(fault));
+        assert_eq!(second, Err(fault));
+        assert_eq!(processor.state, ProcessorState::Halted(fault));
+        assert_eq!(processor.pc, 0x202);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_cycle_unknown_opcode() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.memory.data[0x200] = 0xFF;
+        processor.memory.data[0x201] = 0xFF;
+
+        // Act
+        let result = processor.cycle();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(EmulatorError::UnknownOpcode {
+                pc: 0x200,
+                opcode: 0xFFFF
+            })
+        );
+        assert!(processor.is_halted());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_fetch_out_of_bounds() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.pc = 0xFFF;
+
+        // Act
+        let result = processor.cycle();
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EmulatorError::MemoryOutOfBounds { pc: 0xFFF, .. })
+        ));
+    }
 }
@@ -0,0 +1,89 @@
+/** SplitMix64 generator for CXNN, small enough that its whole state fits in a save state */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rng {
+    /** Seed the generator started from, kept so a run can be reproduced */
+    seed: u64,
+    state: u64,
+}
+impl Rng {
+    pub fn from_seed(seed: u64) -> Rng {
+        Rng { seed, state: seed }
+    }
+    /** Picks a random seed, which can still be read back with `seed` */
+    pub fn from_entropy() -> Rng {
+        let mut bytes = [0; 8];
+        getrandom::getrandom(&mut bytes).expect("Failed to get random seed!");
+
+        Rng::from_seed(u64::from_le_bytes(bytes))
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /** Starts over from the seed */
+    pub fn reset(&mut self) {
+        self.state = self.seed;
+    }
+
+    /** Current position in the sequence, see `set_state` */
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+    pub fn set_state(&mut self, state: u64) {
+        self.state = state;
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_same_seed() {
+        // Arrange
+        let mut a = Rng::from_seed(42);
+        let mut b = Rng::from_seed(42);
+        let mut c = Rng::from_seed(43);
+
+        // Act
+        let a_bytes: Vec<u8> = (0..16).map(|_| a.next_u8()).collect();
+        let b_bytes: Vec<u8> = (0..16).map(|_| b.next_u8()).collect();
+        let c_bytes: Vec<u8> = (0..16).map(|_| c.next_u8()).collect();
+
+        // Assert
+        assert_eq!(a_bytes, b_bytes);
+        assert_ne!(a_bytes, c_bytes);
+        assert_eq!(a.seed(), 42);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_state() {
+        // Arrange
+        let mut rng = Rng::from_seed(7);
+        rng.next_u8();
+        let state = rng.state();
+        let expected = rng.next_u8();
+
+        // Act
+        let mut restored = Rng::from_seed(7);
+        restored.set_state(state);
+
+        // Assert
+        assert_eq!(restored.next_u8(), expected);
+        rng.reset();
+        assert_eq!(rng.state(), 7);
+    }
+}
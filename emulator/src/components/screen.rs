@@ -1,55 +1,35 @@
-use wasm_bindgen::JsCast;
-use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement};
-
 #[derive(Debug)]
-pub struct Screen {
-    context: CanvasRenderingContext2d,
-}
+pub struct Screen;
 impl Screen {
+    /** Low resolution, what every CHIP-8 program starts in */
     pub const WIDTH: usize = 64;
     pub const HEIGHT: usize = 32;
+    /** Largest resolution, the size of the framebuffer */
+    pub const MAX_WIDTH: usize = 128;
+    pub const MAX_HEIGHT: usize = 64;
+}
 
-    pub fn init() -> Screen {
-        Screen {
-            context: {
-                let document = window().unwrap().document().unwrap();
-                let canvas_html_element = document
-                    .query_selector("canvas")
-                    .unwrap()
-                    .expect("Canvas not found!");
-                let canvas = canvas_html_element
-                    .dyn_into::<HtmlCanvasElement>()
-                    .expect("Error casting canvas type!");
-
-                canvas.set_width(Self::WIDTH as u32);
-                canvas.set_height(Self::HEIGHT as u32);
-
-                let ctx = canvas
-                    .get_context("2d")
-                    .unwrap()
-                    .expect("Could not get canvas context!")
-                    .dyn_into::<CanvasRenderingContext2d>()
-                    .expect("Error casting canvas context type!");
-
-                ctx.set_fill_style(&"#000".into());
-                ctx.fill_rect(0.0, 0.0, Self::WIDTH as f64, Self::HEIGHT as f64);
-
-                ctx
-            },
+/** Display mode, switched at runtime by 00FE/00FF or picked by `Emulator::load_rom` */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /** 64x32 */
+    Low,
+    /** SCHIP's 128x64 */
+    High,
+    /** 64x64 of the hi-res CHIP-8 interpreter, which draws across two display pages */
+    TwoPage,
+}
+impl Resolution {
+    pub fn width(self) -> usize {
+        match self {
+            Resolution::Low | Resolution::TwoPage => Screen::WIDTH,
+            Resolution::High => Screen::MAX_WIDTH,
         }
     }
-    pub fn update(&self, gfx: &[u8; Self::HEIGHT * Self::WIDTH]) {
-        for row in 0..Self::HEIGHT {
-            for col in 0..Self::WIDTH {
-                let color = if gfx[row * Self::WIDTH + col] == 1 {
-                    "#fff"
-                } else {
-                    "#000"
-                };
-
-                self.context.set_fill_style(&color.into());
-                self.context.fill_rect(col as f64, row as f64, 1.0, 1.0);
-            }
+    pub fn height(self) -> usize {
+        match self {
+            Resolution::Low => Screen::HEIGHT,
+            Resolution::High | Resolution::TwoPage => Screen::MAX_HEIGHT,
         }
     }
 }
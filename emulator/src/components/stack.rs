@@ -0,0 +1,188 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/** What 2NNN does when every stack level is in use */
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    /** Halt with `EmulatorError::StackOverflow` */
+    Fault,
+    /** The stack pointer wraps around to the bottom and overwrites the oldest levels from there */
+    /** Popping wraps the same way, so 00EE at the bottom returns to the top level instead of underflowing */
+    Wrap,
+    /** The oldest return address is dropped to make room, like SCHIP's fixed 16-level stack */
+    Schip,
+}
+
+/** Return addresses for 2NNN/00EE, a fixed array of `MAX_DEPTH` levels of which `depth` are usable */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stack {
+    entries: [u16; Stack::MAX_DEPTH],
+    /** Stack pointer, the number of levels in use */
+    len: usize,
+    depth: usize,
+    overflow: OverflowPolicy,
+}
+impl Stack {
+    pub const MAX_DEPTH: usize = 16;
+    /** Levels on the COSMAC VIP */
+    pub const VIP_DEPTH: usize = 12;
+
+    /** `depth` is clamped to 1..=`MAX_DEPTH` */
+    pub fn new(depth: usize, overflow: OverflowPolicy) -> Stack {
+        Stack {
+            entries: [0; Stack::MAX_DEPTH],
+            len: 0,
+            depth: depth.clamp(1, Stack::MAX_DEPTH),
+            overflow,
+        }
+    }
+
+    /** Pushes a return address. Returns false if the stack is full and the policy is `Fault` */
+    pub fn push(&mut self, address: u16) -> bool {
+        if self.len == self.depth {
+            match self.overflow {
+                OverflowPolicy::Fault => return false,
+                OverflowPolicy::Wrap => self.len = 0,
+                OverflowPolicy::Schip => {
+                    self.entries.copy_within(1..self.depth, 0);
+                    self.len -= 1;
+                }
+            }
+        }
+        self.entries[self.len] = address;
+        self.len += 1;
+
+        true
+    }
+    /** Pops the latest return address. Returns None if the stack is empty and the policy isn't `Wrap` */
+    pub fn pop(&mut self) -> Option<u16> {
+        if self.len == 0 {
+            match self.overflow {
+                OverflowPolicy::Wrap => self.len = self.depth,
+                OverflowPolicy::Fault | OverflowPolicy::Schip => return None,
+            }
+        }
+        self.len -= 1;
+
+        Some(self.entries[self.len])
+    }
+    /** The return address 00EE would jump to */
+    pub fn top(&self) -> Option<u16> {
+        self.as_slice().last().copied()
+    }
+    pub fn clear(&mut self) {
+        self.entries = [0; Stack::MAX_DEPTH];
+        self.len = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.overflow
+    }
+    /** Levels in use, oldest first */
+    pub fn as_slice(&self) -> &[u16] {
+        &self.entries[..self.len]
+    }
+    /** Every level including unused ones, which keep whatever was last stored there */
+    pub fn entries(&self) -> &[u16; Stack::MAX_DEPTH] {
+        &self.entries
+    }
+    /** Restores a stack from its raw parts, e.g. from a save state */
+    pub fn from_parts(
+        entries: [u16; Stack::MAX_DEPTH],
+        len: usize,
+        depth: usize,
+        overflow: OverflowPolicy,
+    ) -> Stack {
+        let mut stack = Stack::new(depth, overflow);
+        stack.entries = entries;
+        stack.len = len.min(stack.depth);
+
+        stack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OverflowPolicy, Stack};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn full_stack(overflow: OverflowPolicy) -> Stack {
+        let mut stack = Stack::new(Stack::VIP_DEPTH, overflow);
+        for address in 0..Stack::VIP_DEPTH as u16 {
+            stack.push(0x200 + address * 2);
+        }
+
+        stack
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_fault() {
+        // Arrange
+        let mut stack = full_stack(OverflowPolicy::Fault);
+
+        // Act
+        let pushed = stack.push(0x300);
+
+        // Assert
+        assert!(!pushed);
+        assert_eq!(stack.len(), 12);
+        assert_eq!(stack.top(), Some(0x216));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_wrap() {
+        // Arrange
+        let mut stack = full_stack(OverflowPolicy::Wrap);
+
+        // Act
+        let pushed = stack.push(0x300);
+
+        // Assert
+        assert!(pushed);
+        assert_eq!(stack.as_slice(), [0x300]);
+        assert_eq!(stack.entries()[1], 0x202);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_wrap_pop() {
+        // Arrange
+        let mut stack = full_stack(OverflowPolicy::Wrap);
+        stack.push(0x300);
+
+        // Act
+        let popped: Vec<_> = (0..13).map(|_| stack.pop()).collect();
+
+        // Assert
+        assert_eq!(popped[0], Some(0x300));
+        assert_eq!(popped[1], Some(0x216));
+        assert_eq!(popped[11], Some(0x202));
+        assert_eq!(popped[12], Some(0x300));
+        assert_eq!(stack.len(), 0);
+        assert_eq!(Stack::new(4, OverflowPolicy::Fault).pop(), None);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_schip() {
+        // Arrange
+        let mut stack = full_stack(OverflowPolicy::Schip);
+
+        // Act
+        let pushed = stack.push(0x300);
+
+        // Assert
+        assert!(pushed);
+        assert_eq!(stack.len(), 12);
+        assert_eq!(stack.as_slice()[0], 0x202);
+        assert_eq!(stack.pop(), Some(0x300));
+        assert_eq!(stack.pop(), Some(0x216));
+    }
+}
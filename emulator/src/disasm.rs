@@ -0,0 +1,572 @@
+use crate::components::processor::Processor;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/** An instruction decoded the way `Processor::execute` reads it, with its operands */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /** 00E0 */
+    Clear,
+    /** 00EE */
+    Return,
+    /** 00CN */
+    ScrollDown { n: u8 },
+    /** 00DN */
+    ScrollUp { n: u8 },
+    /** 00FB */
+    ScrollRight,
+    /** 00FC */
+    ScrollLeft,
+    /** 00FD */
+    Exit,
+    /** 00FE */
+    LowRes,
+    /** 00FF */
+    HighRes,
+    /** 1NNN */
+    Jump { nnn: u16 },
+    /** 2NNN */
+    Call { nnn: u16 },
+    /** 3XNN */
+    SkipIfEqual { x: u8, nn: u8 },
+    /** 4XNN */
+    SkipIfNotEqual { x: u8, nn: u8 },
+    /** 5XY0 */
+    SkipIfEqualRegister { x: u8, y: u8 },
+    /** 5XY2 */
+    SaveRange { x: u8, y: u8 },
+    /** 5XY3 */
+    LoadRange { x: u8, y: u8 },
+    /** 6XNN */
+    Set { x: u8, nn: u8 },
+    /** 7XNN */
+    Add { x: u8, nn: u8 },
+    /** 8XY0 */
+    Copy { x: u8, y: u8 },
+    /** 8XY1 */
+    Or { x: u8, y: u8 },
+    /** 8XY2 */
+    And { x: u8, y: u8 },
+    /** 8XY3 */
+    Xor { x: u8, y: u8 },
+    /** 8XY4 */
+    AddRegister { x: u8, y: u8 },
+    /** 8XY5 */
+    Sub { x: u8, y: u8 },
+    /** 8XY6 */
+    ShiftRight { x: u8, y: u8 },
+    /** 8XY7 */
+    SubReverse { x: u8, y: u8 },
+    /** 8XYE */
+    ShiftLeft { x: u8, y: u8 },
+    /** 9XY0 */
+    SkipIfNotEqualRegister { x: u8, y: u8 },
+    /** ANNN */
+    SetIndex { nnn: u16 },
+    /** BNNN, or BXNN under the jump quirk, which adds VX instead of V0 */
+    JumpOffset { x: u8, nnn: u16 },
+    /** CXNN */
+    Random { x: u8, nn: u8 },
+    /** DXYN */
+    Draw { x: u8, y: u8, n: u8 },
+    /** EX9E */
+    SkipIfKey { x: u8 },
+    /** EXA1 */
+    SkipIfNotKey { x: u8 },
+    /** F000 NNNN. `decode` only sees the first word, `disassemble` fills in the address from the second */
+    SetIndexLong { nnnn: u16 },
+    /** FN01 */
+    Plane { n: u8 },
+    /** F002 */
+    Audio,
+    /** FX07 */
+    GetDelay { x: u8 },
+    /** FX0A */
+    WaitKey { x: u8 },
+    /** FX15 */
+    SetDelay { x: u8 },
+    /** FX18 */
+    SetSound { x: u8 },
+    /** FX1E */
+    AddIndex { x: u8 },
+    /** FX29 */
+    Font { x: u8 },
+    /** FX30 */
+    BigFont { x: u8 },
+    /** FX33 */
+    Bcd { x: u8 },
+    /** FX3A */
+    Pitch { x: u8 },
+    /** FX55 */
+    Save { x: u8 },
+    /** FX65 */
+    Load { x: u8 },
+    /** FX75 */
+    SaveFlags { x: u8 },
+    /** FX85 */
+    LoadFlags { x: u8 },
+    /** Anything `Processor::execute` faults on, including 0230 outside the 64x64 mode */
+    Unknown { opcode: u16 },
+}
+
+/** Mnemonic flavor for `Instruction::mnemonic` */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syntax {
+    /** Octo's, e.g. `v3 += 0x01` */
+    Octo,
+    /** Cowgod's technical reference, e.g. `ADD V3, #01` */
+    Cowgod,
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Instruction {
+        let (first, rest) = Processor::decode(opcode);
+        let x = ((rest & 0xF00) >> 8) as u8;
+        let y = ((rest & 0x0F0) >> 4) as u8;
+        let n = (rest & 0x00F) as u8;
+        let nn = (rest & 0x0FF) as u8;
+        let nnn = rest;
+
+        match first {
+            0x0 => match rest {
+                0x0E0 => Instruction::Clear,
+                0x0EE => Instruction::Return,
+                0x0C0..=0x0CF => Instruction::ScrollDown { n },
+                0x0D0..=0x0DF => Instruction::ScrollUp { n },
+                0x0FB => Instruction::ScrollRight,
+                0x0FC => Instruction::ScrollLeft,
+                0x0FD => Instruction::Exit,
+                0x0FE => Instruction::LowRes,
+                0x0FF => Instruction::HighRes,
+                _ => Instruction::Unknown { opcode },
+            },
+            0x1 => Instruction::Jump { nnn },
+            0x2 => Instruction::Call { nnn },
+            0x3 => Instruction::SkipIfEqual { x, nn },
+            0x4 => Instruction::SkipIfNotEqual { x, nn },
+            0x5 => match n {
+                0x0 => Instruction::SkipIfEqualRegister { x, y },
+                0x2 => Instruction::SaveRange { x, y },
+                0x3 => Instruction::LoadRange { x, y },
+                _ => Instruction::Unknown { opcode },
+            },
+            0x6 => Instruction::Set { x, nn },
+            0x7 => Instruction::Add { x, nn },
+            0x8 => match n {
+                0x0 => Instruction::Copy { x, y },
+                0x1 => Instruction::Or { x, y },
+                0x2 => Instruction::And { x, y },
+                0x3 => Instruction::Xor { x, y },
+                0x4 => Instruction::AddRegister { x, y },
+                0x5 => Instruction::Sub { x, y },
+                0x6 => Instruction::ShiftRight { x, y },
+                0x7 => Instruction::SubReverse { x, y },
+                0xE => Instruction::ShiftLeft { x, y },
+                _ => Instruction::Unknown { opcode },
+            },
+            // Like `Processor::execute`, any last nibble counts as 9XY0
+            0x9 => Instruction::SkipIfNotEqualRegister { x, y },
+            0xA => Instruction::SetIndex { nnn },
+            0xB => Instruction::JumpOffset { x, nnn },
+            0xC => Instruction::Random { x, nn },
+            0xD => Instruction::Draw { x, y, n },
+            0xE => match nn {
+                0x9E => Instruction::SkipIfKey { x },
+                0xA1 => Instruction::SkipIfNotKey { x },
+                _ => Instruction::Unknown { opcode },
+            },
+            0xF => match nn {
+                0x00 if rest == 0x000 => Instruction::SetIndexLong { nnnn: 0 },
+                0x01 => Instruction::Plane { n: x },
+                0x02 if rest == 0x002 => Instruction::Audio,
+                0x07 => Instruction::GetDelay { x },
+                0x0A => Instruction::WaitKey { x },
+                0x15 => Instruction::SetDelay { x },
+                0x18 => Instruction::SetSound { x },
+                0x1E => Instruction::AddIndex { x },
+                0x29 => Instruction::Font { x },
+                0x30 => Instruction::BigFont { x },
+                0x33 => Instruction::Bcd { x },
+                0x3A => Instruction::Pitch { x },
+                0x55 => Instruction::Save { x },
+                0x65 => Instruction::Load { x },
+                0x75 => Instruction::SaveFlags { x },
+                0x85 => Instruction::LoadFlags { x },
+                _ => Instruction::Unknown { opcode },
+            },
+            _ => Instruction::Unknown { opcode },
+        }
+    }
+
+    /** Bytes the instruction takes up, 4 for F000 NNNN and 2 for any other */
+    pub fn size(self) -> u16 {
+        match self {
+            Instruction::SetIndexLong { .. } => 4,
+            _ => 2,
+        }
+    }
+
+    /** Address that 1NNN, 2NNN and BNNN jump to, before any offset */
+    pub fn target(self) -> Option<u16> {
+        match self {
+            Instruction::Jump { nnn }
+            | Instruction::Call { nnn }
+            | Instruction::JumpOffset { nnn, .. } => Some(nnn),
+            _ => None,
+        }
+    }
+
+    pub fn mnemonic(self, syntax: Syntax) -> String {
+        self.format_with(syntax, &|target| address(syntax, target))
+    }
+
+    /** Like `mnemonic`, with `address` naming the target of jumps and calls */
+    fn format_with(self, syntax: Syntax, address: &dyn Fn(u16) -> String) -> String {
+        match syntax {
+            Syntax::Octo => self.octo(address),
+            Syntax::Cowgod => self.cowgod(address),
+        }
+    }
+    fn octo(self, address: &dyn Fn(u16) -> String) -> String {
+        match self {
+            Instruction::Clear => "clear".to_string(),
+            Instruction::Return => "return".to_string(),
+            Instruction::ScrollDown { n } => format!("scroll-down {}", n),
+            Instruction::ScrollUp { n } => format!("scroll-up {}", n),
+            Instruction::ScrollRight => "scroll-right".to_string(),
+            Instruction::ScrollLeft => "scroll-left".to_string(),
+            Instruction::Exit => "exit".to_string(),
+            Instruction::LowRes => "lores".to_string(),
+            Instruction::HighRes => "hires".to_string(),
+            Instruction::Jump { nnn } => format!("jump {}", address(nnn)),
+            Instruction::Call { nnn } => format!(":call {}", address(nnn)),
+            // Octo's conditions say when the next instruction runs, the opposite of when it's skipped
+            Instruction::SkipIfEqual { x, nn } => format!("if v{:x} != {:#04X} then", x, nn),
+            Instruction::SkipIfNotEqual { x, nn } => format!("if v{:x} == {:#04X} then", x, nn),
+            Instruction::SkipIfEqualRegister { x, y } => format!("if v{:x} != v{:x} then", x, y),
+            Instruction::SaveRange { x, y } => format!("save v{:x} - v{:x}", x, y),
+            Instruction::LoadRange { x, y } => format!("load v{:x} - v{:x}", x, y),
+            Instruction::Set { x, nn } => format!("v{:x} := {:#04X}", x, nn),
+            Instruction::Add { x, nn } => format!("v{:x} += {:#04X}", x, nn),
+            Instruction::Copy { x, y } => format!("v{:x} := v{:x}", x, y),
+            Instruction::Or { x, y } => format!("v{:x} |= v{:x}", x, y),
+            Instruction::And { x, y } => format!("v{:x} &= v{:x}", x, y),
+            Instruction::Xor { x, y } => format!("v{:x} ^= v{:x}", x, y),
+            Instruction::AddRegister { x, y } => format!("v{:x} += v{:x}", x, y),
+            Instruction::Sub { x, y } => format!("v{:x} -= v{:x}", x, y),
+            Instruction::ShiftRight { x, y } => format!("v{:x} >>= v{:x}", x, y),
+            Instruction::SubReverse { x, y } => format!("v{:x} =- v{:x}", x, y),
+            Instruction::ShiftLeft { x, y } => format!("v{:x} <<= v{:x}", x, y),
+            Instruction::SkipIfNotEqualRegister { x, y } => {
+                format!("if v{:x} == v{:x} then", x, y)
+            }
+            Instruction::SetIndex { nnn } => format!("i := {:#05X}", nnn),
+            Instruction::JumpOffset { nnn, .. } => format!("jump0 {}", address(nnn)),
+            Instruction::Random { x, nn } => format!("v{:x} := random {:#04X}", x, nn),
+            Instruction::Draw { x, y, n } => format!("sprite v{:x} v{:x} {}", x, y, n),
+            Instruction::SkipIfKey { x } => format!("if v{:x} -key then", x),
+            Instruction::SkipIfNotKey { x } => format!("if v{:x} key then", x),
+            Instruction::SetIndexLong { nnnn } => format!("i := long {:#06X}", nnnn),
+            Instruction::Plane { n } => format!("plane {}", n),
+            Instruction::Audio => "audio".to_string(),
+            Instruction::GetDelay { x } => format!("v{:x} := delay", x),
+            Instruction::WaitKey { x } => format!("v{:x} := key", x),
+            Instruction::SetDelay { x } => format!("delay := v{:x}", x),
+            Instruction::SetSound { x } => format!("buzzer := v{:x}", x),
+            Instruction::AddIndex { x } => format!("i += v{:x}", x),
+            Instruction::Font { x } => format!("i := hex v{:x}", x),
+            Instruction::BigFont { x } => format!("i := bighex v{:x}", x),
+            Instruction::Bcd { x } => format!("bcd v{:x}", x),
+            Instruction::Pitch { x } => format!("pitch := v{:x}", x),
+            Instruction::Save { x } => format!("save v{:x}", x),
+            Instruction::Load { x } => format!("load v{:x}", x),
+            Instruction::SaveFlags { x } => format!("saveflags v{:x}", x),
+            Instruction::LoadFlags { x } => format!("loadflags v{:x}", x),
+            Instruction::Unknown { opcode } => {
+                format!("{:#04X} {:#04X}", opcode >> 8, opcode & 0xFF)
+            }
+        }
+    }
+    fn cowgod(self, address: &dyn Fn(u16) -> String) -> String {
+        match self {
+            Instruction::Clear => "CLS".to_string(),
+            Instruction::Return => "RET".to_string(),
+            Instruction::ScrollDown { n } => format!("SCD {}", n),
+            Instruction::ScrollUp { n } => format!("SCU {}", n),
+            Instruction::ScrollRight => "SCR".to_string(),
+            Instruction::ScrollLeft => "SCL".to_string(),
+            Instruction::Exit => "EXIT".to_string(),
+            Instruction::LowRes => "LOW".to_string(),
+            Instruction::HighRes => "HIGH".to_string(),
+            Instruction::Jump { nnn } => format!("JP {}", address(nnn)),
+            Instruction::Call { nnn } => format!("CALL {}", address(nnn)),
+            Instruction::SkipIfEqual { x, nn } => format!("SE V{:X}, #{:02X}", x, nn),
+            Instruction::SkipIfNotEqual { x, nn } => format!("SNE V{:X}, #{:02X}", x, nn),
+            Instruction::SkipIfEqualRegister { x, y } => format!("SE V{:X}, V{:X}", x, y),
+            Instruction::SaveRange { x, y } => format!("LD [I], V{:X}-V{:X}", x, y),
+            Instruction::LoadRange { x, y } => format!("LD V{:X}-V{:X}, [I]", x, y),
+            Instruction::Set { x, nn } => format!("LD V{:X}, #{:02X}", x, nn),
+            Instruction::Add { x, nn } => format!("ADD V{:X}, #{:02X}", x, nn),
+            Instruction::Copy { x, y } => format!("LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => format!("OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => format!("AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => format!("XOR V{:X}, V{:X}", x, y),
+            Instruction::AddRegister { x, y } => format!("ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub { x, y } => format!("SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight { x, y } => format!("SHR V{:X}, V{:X}", x, y),
+            Instruction::SubReverse { x, y } => format!("SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft { x, y } => format!("SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipIfNotEqualRegister { x, y } => format!("SNE V{:X}, V{:X}", x, y),
+            Instruction::SetIndex { nnn } => format!("LD I, #{:03X}", nnn),
+            Instruction::JumpOffset { nnn, .. } => format!("JP V0, {}", address(nnn)),
+            Instruction::Random { x, nn } => format!("RND V{:X}, #{:02X}", x, nn),
+            Instruction::Draw { x, y, n } => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipIfKey { x } => format!("SKP V{:X}", x),
+            Instruction::SkipIfNotKey { x } => format!("SKNP V{:X}", x),
+            Instruction::SetIndexLong { nnnn } => format!("LD I, LONG #{:04X}", nnnn),
+            Instruction::Plane { n } => format!("PLANE {}", n),
+            Instruction::Audio => "AUDIO".to_string(),
+            Instruction::GetDelay { x } => format!("LD V{:X}, DT", x),
+            Instruction::WaitKey { x } => format!("LD V{:X}, K", x),
+            Instruction::SetDelay { x } => format!("LD DT, V{:X}", x),
+            Instruction::SetSound { x } => format!("LD ST, V{:X}", x),
+            Instruction::AddIndex { x } => format!("ADD I, V{:X}", x),
+            Instruction::Font { x } => format!("LD F, V{:X}", x),
+            Instruction::BigFont { x } => format!("LD HF, V{:X}", x),
+            Instruction::Bcd { x } => format!("LD B, V{:X}", x),
+            Instruction::Pitch { x } => format!("LD PITCH, V{:X}", x),
+            Instruction::Save { x } => format!("LD [I], V{:X}", x),
+            Instruction::Load { x } => format!("LD V{:X}, [I]", x),
+            Instruction::SaveFlags { x } => format!("LD R, V{:X}", x),
+            Instruction::LoadFlags { x } => format!("LD V{:X}, R", x),
+            Instruction::Unknown { opcode } => format!("DW #{:04X}", opcode),
+        }
+    }
+}
+
+fn address(syntax: Syntax, address: u16) -> String {
+    match syntax {
+        Syntax::Octo => format!("{:#05X}", address),
+        Syntax::Cowgod => format!("#{:03X}", address),
+    }
+}
+
+/** One instruction of a `Listing`, or the odd byte at the end of a ROM */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    /** `None` for a lone trailing byte */
+    pub instruction: Option<Instruction>,
+}
+
+/** A ROM decoded front to back, see `disassemble` */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+    /** Addresses that a jump or call in the ROM leads to */
+    pub labels: BTreeSet<u16>,
+}
+impl Listing {
+    /** Name of the label at `address`, e.g. `label_2A4` */
+    pub fn label(address: u16) -> String {
+        format!("label_{:03X}", address)
+    }
+
+    /** The mnemonic of a line, with jump targets named by their label if one is in the listing */
+    pub fn mnemonic(&self, line: &Line, syntax: Syntax) -> String {
+        match line.instruction {
+            Some(instruction) => {
+                instruction.format_with(syntax, &|target| match self.labels.contains(&target) {
+                    true => Listing::label(target),
+                    false => address(syntax, target),
+                })
+            }
+            None => match syntax {
+                Syntax::Octo => format!("{:#04X}", line.bytes[0]),
+                Syntax::Cowgod => format!("DB #{:02X}", line.bytes[0]),
+            },
+        }
+    }
+}
+impl fmt::Display for Listing {
+    /** One line per instruction: address, raw bytes, Octo and Cowgod mnemonics, with labels on lines of their own */
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            if self.labels.contains(&line.address) {
+                writeln!(f, ": {}", Listing::label(line.address))?;
+            }
+            let bytes: Vec<String> = line
+                .bytes
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            writeln!(
+                f,
+                "{:04X}  {:<11}  {:<28}  {}",
+                line.address,
+                bytes.join(" "),
+                self.mnemonic(line, Syntax::Octo),
+                self.mnemonic(line, Syntax::Cowgod)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/** Decodes a ROM loaded at `address` two bytes at a time, without following the control flow */
+/** Data between the code shows up as whatever instructions its bytes happen to make */
+pub fn disassemble(rom: &[u8], address: u16) -> Listing {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < rom.len() {
+        let line_address = address.wrapping_add(offset as u16);
+        if offset + 1 == rom.len() {
+            lines.push(Line {
+                address: line_address,
+                bytes: vec![rom[offset]],
+                instruction: None,
+            });
+            break;
+        }
+
+        let opcode = (rom[offset] as u16) << 8 | rom[offset + 1] as u16;
+        let mut instruction = Instruction::decode(opcode);
+        if let Instruction::SetIndexLong { nnnn } = &mut instruction {
+            match rom.get(offset + 2..offset + 4) {
+                Some(next) => *nnnn = (next[0] as u16) << 8 | next[1] as u16,
+                // Cut off by the end of the ROM
+                None => instruction = Instruction::Unknown { opcode },
+            }
+        }
+
+        let size = instruction.size() as usize;
+        lines.push(Line {
+            address: line_address,
+            bytes: rom[offset..offset + size].to_vec(),
+            instruction: Some(instruction),
+        });
+        offset += size;
+    }
+
+    let addresses: BTreeSet<u16> = lines.iter().map(|line| line.address).collect();
+    let labels = lines
+        .iter()
+        .filter_map(|line| line.instruction.and_then(Instruction::target))
+        .filter(|target| addresses.contains(target))
+        .collect();
+
+    Listing { lines, labels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disassemble, Instruction, Listing, Syntax};
+    use crate::components::processor::{Processor, ProcessorState};
+    use crate::error::EmulatorError;
+    use crate::quirks::{Platform, Quirks};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_decode() {
+        // Arrange
+        let opcodes = [0x00E0, 0x8AB6, 0xD125, 0xF365, 0x5122, 0x8AB8];
+
+        // Act
+        let instructions: Vec<Instruction> = opcodes.into_iter().map(Instruction::decode).collect();
+
+        // Assert
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::Clear,
+                Instruction::ShiftRight { x: 0xA, y: 0xB },
+                Instruction::Draw { x: 1, y: 2, n: 5 },
+                Instruction::Load { x: 3 },
+                Instruction::SaveRange { x: 1, y: 2 },
+                Instruction::Unknown { opcode: 0x8AB8 },
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_decode_matches_processor() {
+        // Arrange
+        let mut processor = Processor::init_platform(Quirks::xo_chip(), Platform::xo_chip());
+
+        for opcode in 0..=u16::MAX {
+            processor.state = ProcessorState::Running;
+            processor.stack.clear();
+            processor.pc = 0x200;
+            processor.i = 0x200;
+
+            // Act
+            let (first, rest) = Processor::decode(opcode);
+            let result = processor.execute(first, rest);
+
+            // Assert
+            assert_eq!(
+                matches!(result, Err(EmulatorError::UnknownOpcode { .. })),
+                matches!(Instruction::decode(opcode), Instruction::Unknown { .. }),
+                "{:#06X}",
+                opcode
+            );
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_mnemonic() {
+        // Arrange
+        let instruction = Instruction::decode(0x3A0F);
+
+        // Act
+        let octo = instruction.mnemonic(Syntax::Octo);
+        let cowgod = instruction.mnemonic(Syntax::Cowgod);
+
+        // Assert
+        assert_eq!(octo, "if va != 0x0F then");
+        assert_eq!(cowgod, "SE VA, #0F");
+        assert_eq!(
+            Instruction::decode(0x1ABC).mnemonic(Syntax::Octo),
+            "jump 0xABC"
+        );
+        assert_eq!(
+            Instruction::decode(0x2ABC).mnemonic(Syntax::Cowgod),
+            "CALL #ABC"
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_disassemble() {
+        // Arrange
+        let rom = [0x60, 0x01, 0xF0, 0x00, 0x12, 0x34, 0x12, 0x00, 0xFF];
+
+        // Act
+        let listing = disassemble(&rom, 0x200);
+
+        // Assert
+        assert_eq!(listing.lines.len(), 4);
+        assert_eq!(
+            listing.lines[1].instruction,
+            Some(Instruction::SetIndexLong { nnnn: 0x1234 })
+        );
+        assert_eq!(listing.lines[2].address, 0x206);
+        assert_eq!(listing.lines[3].bytes, vec![0xFF]);
+        assert_eq!(
+            listing.labels.iter().copied().collect::<Vec<u16>>(),
+            vec![0x200]
+        );
+        assert_eq!(
+            listing.to_string(),
+            format!(
+                ": {}\n{}\n{}\n{}\n{}\n",
+                Listing::label(0x200),
+                "0200  60 01        v0 := 0x01                    LD V0, #01",
+                "0202  F0 00 12 34  i := long 0x1234              LD I, LONG #1234",
+                "0206  12 00        jump label_200                JP label_200",
+                "0208  FF           0xFF                          DB #FF",
+            )
+        );
+    }
+}
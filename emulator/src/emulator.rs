@@ -0,0 +1,1056 @@
+use crate::components::keypad::Keypad;
+use crate::components::memory::Memory;
+use crate::components::processor::{KeyTrigger, Processor, ProcessorState};
+use crate::components::rng::Rng;
+use crate::components::screen::Resolution;
+use crate::components::stack::{OverflowPolicy, Stack};
+use crate::error::EmulatorError;
+use crate::movie::{InputEvent, Movie};
+use crate::octo::{self, Breakpoint, Monitor, OctoProgram};
+use crate::quirks::{Platform, Quirks};
+use crate::rewind::Rewind;
+use crate::savestate;
+use std::error::Error;
+
+#[derive(Debug)]
+enum MovieMode {
+    Off,
+    Recording(Movie),
+    /** `keypad` is the state of the last input applied, held until the next one */
+    Playing {
+        movie: Movie,
+        next: usize,
+        keypad: u16,
+    },
+}
+
+/** Platform-independent emulator: a processor plus the settings it was built with */
+#[derive(Debug)]
+pub struct Emulator {
+    processor: Processor,
+    clock_hz: u32,
+    font_set: [u8; 80],
+    /** Bytes of memory `load_rom` starts the ROM with, see `set_platform` */
+    memory_size: usize,
+    /** Where `load_rom` puts the ROM and starts running it */
+    load_address: u16,
+    /** 60 Hz frames run since the ROM was loaded */
+    frame: u64,
+    /** Cycles run since the ROM was loaded */
+    cycles: u64,
+    rewind: Option<Rewind>,
+    movie: MovieMode,
+    /** Debug metadata of the Octo source loaded last, empty for plain ROMs */
+    breakpoints: Vec<Breakpoint>,
+    monitors: Vec<Monitor>,
+}
+impl Emulator {
+    pub fn builder() -> EmulatorBuilder {
+        EmulatorBuilder::default()
+    }
+
+    /** Resets the processor and loads the font set and the ROM into memory. The RNG starts over from its seed */
+    /** Ends any movie recording or playback. Leaves the emulator untouched if the ROM doesn't fit in memory */
+    /** A ROM loaded at 0x200 that starts with `1260` runs in the 64x64 hi-res mode from `Memory::HIRES_START_INDEX` */
+    pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), EmulatorError> {
+        let hires = self.load_address == Memory::ROM_BEGIN_INDEX
+            && rom.starts_with(&Memory::HIRES_SIGNATURE);
+        self.load(rom, hires)
+    }
+    /** Compiles Octo (`.8o`) source for the load address and loads it like `load_rom` */
+    /** Keeps its `:breakpoint`s and `:monitor`s until the next load, errors point at a line and column of the source */
+    pub fn load_octo_source(&mut self, source: &str) -> Result<(), Box<dyn Error>> {
+        let program = octo::compile_at(source, self.load_address)?;
+        self.load_octo_program(program)?;
+
+        Ok(())
+    }
+    /** Loads source already compiled for the load address, see `load_octo_source` */
+    pub fn load_octo_program(&mut self, program: OctoProgram) -> Result<(), EmulatorError> {
+        // A `jump main` to 0x260 looks like the hi-res signature, but compiled source never is a hi-res ROM
+        self.load(program.rom, false)?;
+        self.breakpoints = program.breakpoints;
+        self.monitors = program.monitors;
+
+        Ok(())
+    }
+    fn load(&mut self, rom: Vec<u8>, hires: bool) -> Result<(), EmulatorError> {
+        let mut memory = Memory::with_size(self.memory_size);
+        memory.load_font_set(&self.font_set);
+        memory.load_big_font_set(&Memory::BIG_FONT_SET);
+        memory.load_rom_at(rom, self.load_address)?;
+
+        self.processor.memory = memory;
+        self.processor.pc = match hires {
+            true => Memory::HIRES_START_INDEX,
+            false => self.load_address,
+        };
+        self.processor.i = 0;
+        self.processor.stack.clear();
+        self.processor.v = [0; 16];
+        self.processor.delay_timer = 0;
+        self.processor.sound_timer = 0;
+        self.processor.state = ProcessorState::Running;
+        self.processor.rng.reset();
+        self.processor.set_resolution(match hires {
+            true => Resolution::TwoPage,
+            false => Resolution::Low,
+        });
+        self.processor.planes = 1;
+        self.processor.audio_pattern = None;
+        self.processor.pitch = Processor::DEFAULT_PITCH;
+        self.frame = 0;
+        self.cycles = 0;
+        self.movie = MovieMode::Off;
+        self.breakpoints.clear();
+        self.monitors.clear();
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+        self.record_rewind();
+
+        Ok(())
+    }
+
+    /** Loads the ROM and records every keypad change from then on, until `stop_recording` */
+    pub fn start_recording(&mut self, rom: Vec<u8>) -> Result<(), EmulatorError> {
+        let rom_hash = Movie::rom_hash(&rom);
+        self.load_rom(rom)?;
+        self.movie = MovieMode::Recording(Movie {
+            seed: self.seed(),
+            quirks: self.processor.quirks,
+            key_trigger: self.processor.key_trigger,
+            clock_hz: self.clock_hz,
+            load_address: self.load_address,
+            memory_size: self.memory_size,
+            stack_depth: self.processor.stack.depth(),
+            overflow: self.processor.stack.overflow(),
+            font_set: self.font_set,
+            rpl: self.processor.rpl,
+            rom_hash,
+            length: 0,
+            inputs: Vec::new(),
+        });
+
+        Ok(())
+    }
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        match std::mem::replace(&mut self.movie, MovieMode::Off) {
+            MovieMode::Recording(mut movie) => {
+                movie.length = self.cycles;
+                Some(movie)
+            }
+            other => {
+                self.movie = other;
+                None
+            }
+        }
+    }
+    pub fn is_recording(&self) -> bool {
+        matches!(self.movie, MovieMode::Recording(_))
+    }
+    /** Takes over the settings the movie was recorded with, loads the ROM and feeds the recorded inputs back in */
+    /** The host's keypad input is ignored until the movie ends. Leaves the emulator untouched if the ROM doesn't match */
+    pub fn play_movie(&mut self, movie: Movie, rom: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        if Movie::rom_hash(&rom) != movie.rom_hash {
+            return Err("The movie was recorded with a different ROM".into());
+        }
+        let memory_size = movie.memory_size.clamp(Memory::SIZE, Memory::MAX_SIZE);
+        let max = memory_size.saturating_sub(movie.load_address as usize);
+        if rom.len() > max {
+            return Err(EmulatorError::RomTooLarge {
+                size: rom.len(),
+                max,
+            }
+            .into());
+        }
+
+        self.processor.quirks = movie.quirks;
+        self.processor.key_trigger = movie.key_trigger;
+        self.processor.rng = Rng::from_seed(movie.seed);
+        self.processor.stack = Stack::new(movie.stack_depth, movie.overflow);
+        self.processor.rpl = movie.rpl;
+        self.clock_hz = movie.clock_hz;
+        self.load_address = movie.load_address;
+        self.memory_size = memory_size;
+        self.font_set = movie.font_set;
+        self.load_rom(rom)?;
+        self.movie = MovieMode::Playing {
+            movie,
+            next: 0,
+            keypad: 0,
+        };
+
+        Ok(())
+    }
+    pub fn is_playing_movie(&self) -> bool {
+        matches!(self.movie, MovieMode::Playing { .. })
+    }
+    fn update_movie(&mut self) {
+        match &mut self.movie {
+            MovieMode::Off => {}
+            MovieMode::Recording(movie) => {
+                let keypad = self.processor.keypad.state();
+                if movie.inputs.last().map(|input| input.keypad) != Some(keypad) {
+                    movie.inputs.push(InputEvent {
+                        cycle: self.cycles,
+                        keypad,
+                    });
+                }
+            }
+            MovieMode::Playing {
+                movie,
+                next,
+                keypad,
+            } => {
+                while let Some(input) = movie.inputs.get(*next) {
+                    if input.cycle > self.cycles {
+                        break;
+                    }
+                    *keypad = input.keypad;
+                    *next += 1;
+                }
+                self.processor.keypad.set_state(*keypad);
+            }
+        }
+    }
+
+    /** Snapshot of the whole machine in the versioned format described in `savestate` */
+    pub fn save_state(&self) -> Vec<u8> {
+        savestate::save(&self.processor)
+    }
+    /** Restores a snapshot from `save_state`, leaving the emulator as it was if the snapshot is invalid */
+    /** The rewind history is dropped, as it belongs to the timeline that was left */
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        if !matches!(self.movie, MovieMode::Off) {
+            return Err("Can't load a state while a movie is recording or playing".into());
+        }
+        savestate::load(&mut self.processor, bytes)?;
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+
+        Ok(())
+    }
+    /** Same as `load_state`, but the keypad keeps the keys held right now instead of the saved ones */
+    /** For hosts that only report key changes, where a key saved as held would stay down until pressed again */
+    pub fn load_state_keep_input(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let keypad = self.processor.keypad;
+        self.load_state(bytes)?;
+        self.processor.keypad = keypad;
+
+        Ok(())
+    }
+
+    /** Runs one instruction, see `Processor::cycle` */
+    pub fn cycle(&mut self) -> Result<(), EmulatorError> {
+        if let Some(err) = self.processor.fault() {
+            return Err(err);
+        }
+        self.update_movie();
+        let result = self.processor.cycle();
+        self.cycles += 1;
+
+        if let MovieMode::Playing { movie, .. } = &self.movie {
+            if self.cycles >= movie.length {
+                self.movie = MovieMode::Off;
+            }
+        }
+
+        result
+    }
+    /** Runs one 60 Hz frame worth of instructions at the configured clock rate, then ticks the timers */
+    /** Stops at a fault, after which the emulator stays halted until a ROM or state is loaded */
+    pub fn run_frame(&mut self) -> Result<(), EmulatorError> {
+        for _ in 0..self.cycles_per_frame() {
+            self.cycle()?;
+        }
+        self.tick_timers();
+
+        Ok(())
+    }
+    /** Decrements the delay and sound timers, for hosts that call `cycle` themselves */
+    pub fn tick_timers(&mut self) {
+        self.processor.tick_timers();
+        self.end_frame();
+    }
+    fn end_frame(&mut self) {
+        self.frame += 1;
+        self.record_rewind();
+    }
+    /** 60 Hz frames run since the ROM was loaded */
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    /** Cycles run since the ROM was loaded */
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /** Goes back at least `frames` frames, or as far as the history goes. Returns how many frames were undone */
+    /** Does nothing while a movie is recording or playing. Like `load_state_keep_input`, the keypad keeps the keys held right now */
+    /** A snapshot that fails to load drops the whole history, so nothing is undone */
+    pub fn rewind(&mut self, frames: u64) -> u64 {
+        let Some(rewind) = &mut self.rewind else {
+            return 0;
+        };
+        if !matches!(self.movie, MovieMode::Off) {
+            return 0;
+        }
+        let Some((frame, cycles, state)) = rewind.rewind_to(self.frame.saturating_sub(frames))
+        else {
+            return 0;
+        };
+        if frame >= self.frame {
+            return 0;
+        }
+        let keypad = self.processor.keypad;
+        if savestate::load(&mut self.processor, state).is_err() {
+            rewind.clear();
+            return 0;
+        }
+        self.processor.keypad = keypad;
+
+        let rewound = self.frame - frame;
+        self.frame = frame;
+        self.cycles = cycles;
+        rewound
+    }
+    pub fn rewind_history(&self) -> Option<&Rewind> {
+        self.rewind.as_ref()
+    }
+    fn record_rewind(&mut self) {
+        if let Some(rewind) = &mut self.rewind {
+            if rewind.is_due(self.frame) {
+                rewind.record(self.frame, self.cycles, savestate::save(&self.processor));
+            }
+        }
+    }
+
+    pub fn key_down(&mut self, key: u8) {
+        self.processor.keypad.press(key);
+    }
+    pub fn key_up(&mut self, key: u8) {
+        self.processor.keypad.release(key);
+    }
+    /** Pressed keys as a bit mask, bit 0 is key 0x0 and bit 15 is key 0xF */
+    pub fn keypad_state(&self) -> u16 {
+        self.processor.keypad.state()
+    }
+    /** Whether the program is blocked on FX0A until a key is entered */
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.processor.is_waiting_for_key()
+    }
+    pub fn is_halted(&self) -> bool {
+        self.processor.is_halted()
+    }
+    /** Whether the ROM ended itself with 00FD */
+    pub fn is_exited(&self) -> bool {
+        self.processor.is_exited()
+    }
+    /** The fault that halted the emulator, if any */
+    pub fn fault(&self) -> Option<EmulatorError> {
+        self.processor.fault()
+    }
+    pub fn keypad(&self) -> &Keypad {
+        &self.processor.keypad
+    }
+    pub fn keypad_mut(&mut self) -> &mut Keypad {
+        &mut self.processor.keypad
+    }
+
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+    pub fn processor_mut(&mut self) -> &mut Processor {
+        &mut self.processor
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.processor.quirks
+    }
+    /** Takes effect from the next instruction, the stack and FX0A key trigger are left as they are */
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.processor.quirks = quirks;
+    }
+    /** Switches the key trigger and the stack depth right away, emptying the stack. Memory changes size on the next `load_rom` */
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.memory_size = platform.memory_size;
+        self.processor.key_trigger = platform.key_trigger;
+        self.processor.stack = Stack::new(platform.stack_depth, self.processor.stack.overflow());
+    }
+    pub fn load_address(&self) -> u16 {
+        self.load_address
+    }
+    /** Takes effect on the next `load_rom` */
+    pub fn set_load_address(&mut self, address: u16) {
+        self.load_address = address;
+    }
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+    pub fn monitors(&self) -> &[Monitor] {
+        &self.monitors
+    }
+    /** The breakpoint at the instruction about to run, for hosts to pause on */
+    pub fn breakpoint(&self) -> Option<&Breakpoint> {
+        self.breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.address == self.processor.pc)
+    }
+    /** Seed of the CXNN random number generator, either the one given to the builder or one picked at random */
+    pub fn seed(&self) -> u64 {
+        self.processor.rng.seed()
+    }
+    /** How many cycles make up one 60 Hz frame at the configured clock rate */
+    pub fn cycles_per_frame(&self) -> u32 {
+        (self.clock_hz / 60).max(1)
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.processor.pc
+    }
+    pub fn i(&self) -> u16 {
+        self.processor.i
+    }
+    /** Registers V0 to VF */
+    pub fn v(&self) -> &[u8; 16] {
+        &self.processor.v
+    }
+    /** Call stack, see `Stack::as_slice` for the levels in use and `Stack::entries` for the whole array */
+    pub fn stack(&self) -> &Stack {
+        &self.processor.stack
+    }
+    pub fn delay_timer(&self) -> u8 {
+        self.processor.delay_timer
+    }
+    pub fn sound_timer(&self) -> u8 {
+        self.processor.sound_timer
+    }
+    pub fn is_sound_playing(&self) -> bool {
+        self.processor.is_sound_playing()
+    }
+    /** The XO-CHIP audio pattern and the samples per second to play it at, `None` until the ROM sets one */
+    pub fn audio_pattern(&self) -> Option<([u8; 16], f64)> {
+        self.processor
+            .audio_pattern
+            .map(|pattern| (pattern, self.processor.audio_sample_rate()))
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.processor.memory.data
+    }
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.processor.memory.data
+    }
+
+    /** Framebuffer in row-major order, one byte per pixel, see `width` and `height` */
+    pub fn framebuffer(&self) -> &[u8] {
+        self.processor.framebuffer()
+    }
+    /** Width of the current resolution, which the ROM can switch at any time */
+    pub fn width(&self) -> usize {
+        self.processor.width()
+    }
+    pub fn height(&self) -> usize {
+        self.processor.height()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EmulatorBuilder {
+    quirks: Quirks,
+    clock_hz: u32,
+    font_set: [u8; 80],
+    seed: Option<u64>,
+    platform: Platform,
+    overflow: OverflowPolicy,
+    rewind: Option<(usize, u32)>,
+    load_address: u16,
+}
+impl Default for EmulatorBuilder {
+    fn default() -> EmulatorBuilder {
+        EmulatorBuilder {
+            quirks: Quirks::default(),
+            clock_hz: 500,
+            font_set: Memory::FONT_SET,
+            seed: None,
+            platform: Platform::default(),
+            overflow: OverflowPolicy::Fault,
+            rewind: None,
+            load_address: Memory::ROM_BEGIN_INDEX,
+        }
+    }
+}
+impl EmulatorBuilder {
+    /** Interpreter behaviors to follow, SCHIP 1.1 by default */
+    pub fn quirks(mut self, quirks: Quirks) -> EmulatorBuilder {
+        self.quirks = quirks;
+        self
+    }
+    /** Instructions per second, 500 by default */
+    pub fn clock_hz(mut self, clock_hz: u32) -> EmulatorBuilder {
+        self.clock_hz = clock_hz;
+        self
+    }
+    /** Replaces the built-in hex digit sprites, 5 bytes per digit from 0 to F */
+    pub fn font_set(mut self, font_set: [u8; 80]) -> EmulatorBuilder {
+        self.font_set = font_set;
+        self
+    }
+    /** Seeds the random number generator used by CXNN, otherwise it's seeded from entropy */
+    pub fn seed(mut self, seed: u64) -> EmulatorBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /** Memory size, stack depth and key trigger of a machine, `Platform::schip()` by default */
+    /** Pick it along with the quirks, e.g. `Platform::preset("xochip")` for 64 KB. The settings below override parts of it */
+    pub fn platform(mut self, platform: Platform) -> EmulatorBuilder {
+        self.platform = platform;
+        self
+    }
+    /** Whether FX0A finishes on key press or release */
+    pub fn key_trigger(mut self, key_trigger: KeyTrigger) -> EmulatorBuilder {
+        self.platform.key_trigger = key_trigger;
+        self
+    }
+
+    /** Stack levels and what happens when they run out, by default the platform's levels faulting on overflow */
+    pub fn stack(mut self, depth: usize, overflow: OverflowPolicy) -> EmulatorBuilder {
+        self.platform.stack_depth = depth;
+        self.overflow = overflow;
+        self
+    }
+    /** Bytes of memory, clamped to 4 KB..=64 KB */
+    pub fn memory_size(mut self, size: usize) -> EmulatorBuilder {
+        self.platform.memory_size = size;
+        self
+    }
+    /** Where ROMs load and start, 0x200 by default. `Memory::ETI_660_ROM_BEGIN_INDEX` for ETI-660 programs */
+    pub fn load_address(mut self, address: u16) -> EmulatorBuilder {
+        self.load_address = address;
+        self
+    }
+    /** Keeps `depth` snapshots taken every `interval` frames for `Emulator::rewind`, off by default */
+    pub fn rewind(mut self, depth: usize, interval: u32) -> EmulatorBuilder {
+        self.rewind = Some((depth, interval));
+        self
+    }
+
+    pub fn build(self) -> Emulator {
+        let mut processor = Processor::init_platform(self.quirks, self.platform);
+        if let Some(seed) = self.seed {
+            processor.rng = Rng::from_seed(seed);
+        }
+        processor.stack = Stack::new(self.platform.stack_depth, self.overflow);
+        processor.memory.load_font_set(&self.font_set);
+        processor.memory.load_big_font_set(&Memory::BIG_FONT_SET);
+        processor.pc = self.load_address;
+        processor.instruction_pc = self.load_address;
+
+        Emulator {
+            processor,
+            clock_hz: self.clock_hz,
+            font_set: self.font_set,
+            memory_size: self.platform.memory_size,
+            load_address: self.load_address,
+            frame: 0,
+            cycles: 0,
+            movie: MovieMode::Off,
+            breakpoints: Vec::new(),
+            monitors: Vec::new(),
+            rewind: self
+                .rewind
+                .map(|(depth, interval)| Rewind::new(depth, interval)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Emulator;
+    use crate::components::memory::Memory;
+    use crate::components::stack::{OverflowPolicy, Stack};
+    use crate::error::EmulatorError;
+    use crate::movie::Movie;
+    use crate::quirks::{Platform, Quirks};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_builder() {
+        // Arrange
+        let font_set = [0xAA; 80];
+
+        // Act
+        let emulator = Emulator::builder()
+            .quirks(Quirks::cosmac_vip())
+            .clock_hz(720)
+            .font_set(font_set)
+            .build();
+
+        // Assert
+        assert_eq!(emulator.quirks(), Quirks::cosmac_vip());
+        assert_eq!(emulator.clock_hz(), 720);
+        assert_eq!(emulator.cycles_per_frame(), 12);
+        assert_eq!(
+            emulator.memory()[Memory::FONT_BEGIN_INDEX as usize
+                ..Memory::FONT_BEGIN_INDEX as usize + font_set.len()],
+            font_set
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_memory_size() {
+        // Arrange
+        let mut xo_chip = Emulator::builder()
+            .quirks(Quirks::xo_chip())
+            .platform(Platform::xo_chip())
+            .build();
+        let mut small = Emulator::builder()
+            .quirks(Quirks::xo_chip())
+            .platform(Platform::xo_chip())
+            .memory_size(Memory::SIZE)
+            .build();
+        let mut emulator = Emulator::builder().build();
+        let rom = vec![0x12; 0x2000];
+
+        // Act
+        let xo_chip_result = xo_chip.load_rom(rom.clone());
+        let small_result = small.load_rom(rom.clone());
+        emulator.set_quirks(Quirks::xo_chip());
+        let quirks_only_result = emulator.load_rom(rom.clone());
+        emulator.set_platform(Platform::xo_chip());
+        emulator.set_quirks(Quirks {
+            display_wait: true,
+            ..Quirks::xo_chip()
+        });
+        let switched_result = emulator.load_rom(rom);
+
+        // Assert
+        assert!(xo_chip_result.is_ok());
+        assert_eq!(xo_chip.memory().len(), Memory::MAX_SIZE);
+        assert!(small_result.is_err());
+        assert!(
+            quirks_only_result.is_err(),
+            "The quirks don't pick the memory size"
+        );
+        assert!(
+            switched_result.is_ok(),
+            "Memory follows the platform on load"
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_rom() {
+        // Arrange
+        let mut emulator = Emulator::builder().build();
+        emulator.processor_mut().pc = 0x300;
+        emulator.processor_mut().gfx[0] = 1;
+
+        // Act
+        emulator.load_rom(vec![0x60, 0x2A]).unwrap();
+        emulator.cycle().unwrap();
+
+        // Assert
+        assert_eq!(emulator.pc(), Memory::ROM_BEGIN_INDEX + 2);
+        assert_eq!(emulator.v()[0], 0x2A);
+        assert!(emulator.framebuffer().iter().all(|pixel| *pixel == 0));
+        assert_eq!(
+            emulator.memory()[Memory::FONT_BEGIN_INDEX as usize],
+            Memory::FONT_SET[0]
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_address() {
+        // Arrange
+        let mut emulator = Emulator::builder()
+            .load_address(Memory::ETI_660_ROM_BEGIN_INDEX)
+            .build();
+
+        // Act
+        emulator.load_rom(vec![0x60, 0x2A]).unwrap();
+        emulator.cycle().unwrap();
+
+        // Assert
+        assert_eq!(emulator.pc(), Memory::ETI_660_ROM_BEGIN_INDEX + 2);
+        assert_eq!(emulator.v()[0], 0x2A);
+        assert_eq!(emulator.memory()[Memory::ROM_BEGIN_INDEX as usize], 0);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_octo_source() {
+        // Arrange
+        let mut emulator = Emulator::builder()
+            .load_address(Memory::ETI_660_ROM_BEGIN_INDEX)
+            .build();
+
+        // Act
+        emulator
+            .load_octo_source(": main v0 := 0x2A :breakpoint done : end jump end")
+            .unwrap();
+        emulator.cycle().unwrap();
+        let v0 = emulator.v()[0];
+        let breakpoint = emulator
+            .breakpoint()
+            .map(|breakpoint| breakpoint.name.clone());
+        let error = emulator.load_octo_source(": main\n jump nowhere");
+        emulator.load_rom(vec![0x12, 0x00]).unwrap();
+
+        // Assert
+        assert_eq!(v0, 0x2A);
+        assert_eq!(breakpoint.as_deref(), Some("done"));
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "2:7: Undefined name 'nowhere'"
+        );
+        assert!(emulator.breakpoints().is_empty());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_rom_hires() {
+        // Arrange
+        let mut rom = vec![0; (Memory::HIRES_START_INDEX - Memory::ROM_BEGIN_INDEX) as usize];
+        rom[..2].copy_from_slice(&Memory::HIRES_SIGNATURE);
+        rom.extend([0x60, 0x2A, 0x02, 0x30]);
+        let mut emulator = Emulator::builder().build();
+
+        // Act
+        emulator.load_rom(rom).unwrap();
+        emulator.processor_mut().gfx[64 * 63] = 1;
+        emulator.cycle().unwrap();
+        emulator.cycle().unwrap();
+
+        // Assert
+        assert_eq!((emulator.width(), emulator.height()), (64, 64));
+        assert_eq!(emulator.pc(), Memory::HIRES_START_INDEX + 4);
+        assert_eq!(emulator.v()[0], 0x2A);
+        assert!(emulator.framebuffer().iter().all(|pixel| *pixel == 0));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_keypad_per_instance() {
+        // Arrange
+        let mut first = Emulator::builder().build();
+        let second = Emulator::builder().build();
+
+        // Act
+        first.key_down(0x5);
+
+        // Assert
+        assert!(first.keypad().is_pressed(0x5));
+        assert!(!second.keypad().any_pressed());
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_key_up_keeps_other_keys() {
+        // Arrange
+        let mut emulator = Emulator::builder().build();
+        emulator.key_down(0x5);
+        emulator.key_down(0x6);
+
+        // Act
+        emulator.key_up(0x6);
+
+        // Assert
+        assert_eq!(emulator.keypad_state(), 1 << 0x5);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_run_frame() {
+        // Arrange
+        let mut emulator = Emulator::builder().clock_hz(600).build();
+        // 7001: add 1 to V0, repeated
+        emulator.load_rom([0x70, 0x01].repeat(20)).unwrap();
+        emulator.processor_mut().delay_timer = 0x2;
+
+        // Act
+        emulator.run_frame().unwrap();
+
+        // Assert
+        assert_eq!(emulator.v()[0], 10);
+        assert_eq!(emulator.delay_timer(), 0x1);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_seed() {
+        // Arrange
+        let rom = vec![0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF];
+        let mut first = Emulator::builder().seed(42).build();
+        let mut second = Emulator::builder().seed(42).build();
+        first.load_rom(rom.clone()).unwrap();
+        second.load_rom(rom).unwrap();
+
+        // Act
+        for _ in 0..3 {
+            first.cycle().unwrap();
+            second.cycle().unwrap();
+        }
+
+        // Assert
+        assert_eq!(first.v(), second.v());
+        assert_eq!(first.seed(), 42);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_rom_restarts_rng() {
+        // Arrange
+        let rom = vec![0xC0, 0xFF, 0xC1, 0xFF];
+        let mut emulator = Emulator::builder().build();
+        emulator.load_rom(rom.clone()).unwrap();
+        emulator.cycle().unwrap();
+        emulator.cycle().unwrap();
+        let first_run = *emulator.v();
+
+        // Act
+        emulator.load_rom(rom).unwrap();
+        emulator.cycle().unwrap();
+        emulator.cycle().unwrap();
+
+        // Assert
+        assert_eq!(*emulator.v(), first_run);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_save_state() {
+        // Arrange
+        let rom = vec![0xC0, 0xFF, 0x70, 0x01, 0x12, 0x00];
+        let mut emulator = Emulator::builder().build();
+        emulator.load_rom(rom).unwrap();
+        emulator.run_frame().unwrap();
+        let state = emulator.save_state();
+        emulator.run_frame().unwrap();
+        let expected = *emulator.v();
+
+        // Act
+        emulator.run_frame().unwrap();
+        emulator.load_state(&state).unwrap();
+        emulator.run_frame().unwrap();
+
+        // Assert
+        assert_eq!(*emulator.v(), expected);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_load_state_keypad() {
+        // Arrange
+        let mut emulator = Emulator::builder().build();
+        emulator.load_rom(vec![0x12, 0x00]).unwrap();
+        emulator.key_down(0x5);
+        let state = emulator.save_state();
+        emulator.key_up(0x5);
+        emulator.key_down(0xA);
+
+        // Act
+        emulator.load_state_keep_input(&state).unwrap();
+        let kept = emulator.keypad_state();
+        emulator.load_state(&state).unwrap();
+        let loaded = emulator.keypad_state();
+
+        // Assert
+        assert_eq!(kept, 1 << 0xA);
+        assert_eq!(loaded, 1 << 0x5);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_rewind_keeps_keypad() {
+        // Arrange
+        let mut emulator = Emulator::builder().rewind(10, 1).build();
+        emulator.load_rom(vec![0x12, 0x00]).unwrap();
+        emulator.key_down(0x5);
+        emulator.run_frame().unwrap();
+        emulator.run_frame().unwrap();
+        emulator.key_up(0x5);
+
+        // Act
+        emulator.rewind(1);
+
+        // Assert
+        assert_eq!(emulator.frame(), 1);
+        assert_eq!(emulator.keypad_state(), 0);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_rewind() {
+        // Arrange
+        let rom = vec![0x70, 0x01, 0x12, 0x00];
+        let mut emulator = Emulator::builder().clock_hz(60).rewind(10, 5).build();
+        emulator.load_rom(rom).unwrap();
+        for _ in 0..30 {
+            emulator.run_frame().unwrap();
+        }
+
+        // Act
+        let rewound = emulator.rewind(12);
+        let v0 = emulator.v()[0];
+
+        // Assert
+        assert_eq!(rewound, 15);
+        assert_eq!(emulator.frame(), 15);
+        assert_eq!(v0, 8);
+        assert_eq!(emulator.rewind(100), 15);
+        assert_eq!(emulator.rewind(1), 0);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_rewind_cycles() {
+        // Arrange
+        let mut emulator = Emulator::builder().clock_hz(600).rewind(10, 1).build();
+        emulator.load_rom(vec![0x12, 0x00]).unwrap();
+        for cycles in [3, 25, 7] {
+            for _ in 0..cycles {
+                emulator.cycle().unwrap();
+            }
+            emulator.tick_timers();
+        }
+
+        // Act
+        emulator.rewind(1);
+
+        // Assert
+        assert_eq!(emulator.frame(), 2);
+        assert_eq!(emulator.cycles(), 28);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_movie() {
+        // Waits for a key, then draws random bytes into V1 to V3
+        let rom = vec![
+            0xF0, 0x0A, 0xC1, 0xFF, 0xC2, 0xFF, 0xE0, 0x9E, 0x12, 0x06, 0xC3, 0xFF, 0x12, 0x0C,
+        ];
+        let mut recorder = Emulator::builder().seed(7).build();
+        recorder.start_recording(rom.clone()).unwrap();
+        for frame in 0..20 {
+            match frame {
+                3 => recorder.key_down(0x5),
+                5 => recorder.key_up(0x5),
+                9 => recorder.key_down(0x2),
+                _ => {}
+            }
+            recorder.run_frame().unwrap();
+        }
+        let movie = recorder.stop_recording().unwrap();
+        let bytes = movie.to_bytes();
+
+        // Act
+        let mut player = Emulator::builder().clock_hz(1000).build();
+        player
+            .play_movie(Movie::from_bytes(&bytes).unwrap(), rom.clone())
+            .unwrap();
+        for _ in 0..20 {
+            player.key_down(0xF);
+            player.run_frame().unwrap();
+        }
+
+        // Assert
+        assert_eq!(movie.inputs.len(), 4);
+        assert_eq!(player.v(), recorder.v());
+        assert_eq!(player.pc(), recorder.pc());
+        assert_eq!(player.seed(), 7);
+        assert!(!player.is_playing_movie());
+        assert!(player.play_movie(movie, vec![0x00]).is_err());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_movie_machine() {
+        // Arrange
+        let rom = vec![0x12, 0x00];
+        let mut recorder = Emulator::builder()
+            .platform(Platform::xo_chip())
+            .font_set([0xAA; 80])
+            .stack(Stack::VIP_DEPTH, OverflowPolicy::Wrap)
+            .build();
+        recorder.processor_mut().rpl = [0x07; 16];
+        recorder.start_recording(rom.clone()).unwrap();
+        recorder.run_frame().unwrap();
+        let movie = recorder.stop_recording().unwrap();
+        let mut too_large = movie.clone();
+        too_large.memory_size = Memory::SIZE;
+        too_large.clock_hz = 1000;
+        let large_rom = vec![0; 8192];
+        too_large.rom_hash = Movie::rom_hash(&large_rom);
+
+        // Act
+        let mut player = Emulator::builder().build();
+        let result = player.play_movie(too_large, large_rom);
+        let clock_hz = player.clock_hz;
+        player.play_movie(movie, rom).unwrap();
+
+        // Assert
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<EmulatorError>(),
+            Some(EmulatorError::RomTooLarge { size: 8192, .. })
+        ));
+        assert_eq!(clock_hz, 500);
+        assert_eq!(player.memory_size, Memory::MAX_SIZE);
+        assert_eq!(player.stack().depth(), Stack::VIP_DEPTH);
+        assert_eq!(player.stack().overflow(), OverflowPolicy::Wrap);
+        assert_eq!(player.processor().rpl, [0x07; 16]);
+        let font = Memory::FONT_BEGIN_INDEX as usize;
+        assert_eq!(player.memory()[font..font + 80], [0xAA; 80]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_halt() {
+        // Arrange
+        let mut emulator = Emulator::builder().build();
+        emulator.load_rom(vec![0x00, 0xEE]).unwrap();
+        let too_large = vec![0; 4096];
+
+        // Act
+        let result = emulator.run_frame();
+        let rom_result = emulator.load_rom(too_large);
+
+        // Assert
+        let fault = EmulatorError::StackUnderflow {
+            pc: 0x200,
+            opcode: 0x00EE,
+        };
+        assert_eq!(result, Err(fault));
+        assert_eq!(emulator.fault(), Some(fault));
+        assert!(matches!(
+            rom_result,
+            Err(EmulatorError::RomTooLarge { size: 4096, .. })
+        ));
+        assert!(emulator.is_halted(), "A ROM that doesn't fit is not loaded");
+        emulator.load_rom(vec![0x12, 0x00]).unwrap();
+        assert!(emulator.run_frame().is_ok());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_stack() {
+        // Arrange
+        // 2200: call self forever
+        let rom = vec![0x22, 0x00];
+        let mut vip = Emulator::builder()
+            .quirks(Quirks::cosmac_vip())
+            .platform(Platform::cosmac_vip())
+            .clock_hz(1200)
+            .build();
+        let mut wrapping = Emulator::builder()
+            .clock_hz(1200)
+            .stack(Stack::MAX_DEPTH, OverflowPolicy::Wrap)
+            .build();
+        vip.load_rom(rom.clone()).unwrap();
+        wrapping.load_rom(rom).unwrap();
+
+        // Act
+        let vip_result = vip.run_frame();
+        let wrapping_result = wrapping.run_frame();
+
+        // Assert
+        assert!(matches!(
+            vip_result,
+            Err(EmulatorError::StackOverflow { pc: 0x200, .. })
+        ));
+        assert_eq!(vip.stack().len(), 12);
+        assert!(wrapping_result.is_ok());
+        assert_eq!(wrapping.stack().entries(), &[0x202; 16]);
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+/** Faults that stop the processor, see `ProcessorState::Halted` */
+/** `pc` is the address of the faulting instruction and `opcode` the instruction itself */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmulatorError {
+    /** 00EE with nothing to return to */
+    StackUnderflow { pc: u16, opcode: u16 },
+    /** 2NNN with every stack level in use */
+    StackOverflow { pc: u16, opcode: u16 },
+    /** A read or write past the end of memory, `address` is the first byte out of range */
+    MemoryOutOfBounds {
+        pc: u16,
+        opcode: u16,
+        address: usize,
+    },
+    /** An opcode that isn't an instruction of any supported platform */
+    UnknownOpcode { pc: u16, opcode: u16 },
+    /** A ROM that doesn't fit between its load address and the end of memory */
+    RomTooLarge { size: usize, max: usize },
+}
+impl EmulatorError {
+    /** Address of the faulting instruction, if the fault came from one */
+    pub fn pc(&self) -> Option<u16> {
+        match *self {
+            EmulatorError::StackUnderflow { pc, .. }
+            | EmulatorError::StackOverflow { pc, .. }
+            | EmulatorError::MemoryOutOfBounds { pc, .. }
+            | EmulatorError::UnknownOpcode { pc, .. } => Some(pc),
+            EmulatorError::RomTooLarge { .. } => None,
+        }
+    }
+    pub fn opcode(&self) -> Option<u16> {
+        match *self {
+            EmulatorError::StackUnderflow { opcode, .. }
+            | EmulatorError::StackOverflow { opcode, .. }
+            | EmulatorError::MemoryOutOfBounds { opcode, .. }
+            | EmulatorError::UnknownOpcode { opcode, .. } => Some(opcode),
+            EmulatorError::RomTooLarge { .. } => None,
+        }
+    }
+}
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EmulatorError::StackUnderflow { pc, opcode } => write!(
+                f,
+                "Stack underflow: {:#06X} at {:#06X} returned with an empty stack",
+                opcode, pc
+            ),
+            EmulatorError::StackOverflow { pc, opcode } => write!(
+                f,
+                "Stack overflow: {:#06X} at {:#06X} called with a full stack",
+                opcode, pc
+            ),
+            EmulatorError::MemoryOutOfBounds {
+                pc,
+                opcode,
+                address,
+            } => write!(
+                f,
+                "Memory out of bounds: {:#06X} at {:#06X} accessed {:#06X}",
+                opcode, pc, address
+            ),
+            EmulatorError::UnknownOpcode { pc, opcode } => {
+                write!(f, "Opcode {:#06X} at {:#06X} not recognized", opcode, pc)
+            }
+            EmulatorError::RomTooLarge { size, max } => write!(
+                f,
+                "ROM is too large: {} bytes, at most {} fit in memory",
+                size, max
+            ),
+        }
+    }
+}
+impl std::error::Error for EmulatorError {}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+/** Maps host keys to CHIP-8 keys. Several host keys can share one CHIP-8 key. */
+/** Host keys are `KeyboardEvent.code` values like `KeyQ` or `ArrowUp`, or typed characters like `q` */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: BTreeMap<String, u8>,
+}
+impl Default for Keymap {
+    /** Physical 1234/QWER/ASDF/ZXCV block, which sits in the same place on every layout */
+    fn default() -> Keymap {
+        Keymap::from_rows([
+            ["Digit1", "Digit2", "Digit3", "Digit4"],
+            ["KeyQ", "KeyW", "KeyE", "KeyR"],
+            ["KeyA", "KeyS", "KeyD", "KeyF"],
+            ["KeyZ", "KeyX", "KeyC", "KeyV"],
+        ])
+    }
+}
+impl Keymap {
+    /** Hex keypad layout, row by row, as in the rows passed to `from_rows` */
+    pub const KEYPAD_ROWS: [[u8; 4]; 4] = [
+        [0x1, 0x2, 0x3, 0xC],
+        [0x4, 0x5, 0x6, 0xD],
+        [0x7, 0x8, 0x9, 0xE],
+        [0xA, 0x0, 0xB, 0xF],
+    ];
+    pub const PRESETS: [&'static str; 3] = ["qwerty", "azerty", "dvorak"];
+
+    pub fn empty() -> Keymap {
+        Keymap {
+            bindings: BTreeMap::new(),
+        }
+    }
+    /** Binds a 4x4 block of host keys onto the hex keypad, see `KEYPAD_ROWS` */
+    pub fn from_rows(rows: [[&str; 4]; 4]) -> Keymap {
+        let mut keymap = Keymap::empty();
+        for (row, keys) in rows.iter().enumerate() {
+            for (col, host_key) in keys.iter().enumerate() {
+                keymap.bind(host_key, Keymap::KEYPAD_ROWS[row][col]);
+            }
+        }
+
+        keymap
+    }
+    /** Character based layouts, for hosts that only see typed characters */
+    pub fn preset(name: &str) -> Result<Keymap, Box<dyn std::error::Error>> {
+        let keymap = match name.to_lowercase().as_str() {
+            "qwerty" => Keymap::from_rows([
+                ["1", "2", "3", "4"],
+                ["q", "w", "e", "r"],
+                ["a", "s", "d", "f"],
+                ["z", "x", "c", "v"],
+            ]),
+            "azerty" => {
+                let mut keymap = Keymap::from_rows([
+                    ["&", "é", "\"", "'"],
+                    ["a", "z", "e", "r"],
+                    ["q", "s", "d", "f"],
+                    ["w", "x", "c", "v"],
+                ]);
+                // The number row needs shift for digits on AZERTY, so accept both
+                for (host_key, key) in [("1", 0x1), ("2", 0x2), ("3", 0x3), ("4", 0xC)] {
+                    keymap.bind(host_key, key);
+                }
+                keymap
+            }
+            "dvorak" => Keymap::from_rows([
+                ["1", "2", "3", "4"],
+                ["'", ",", ".", "p"],
+                ["a", "o", "e", "u"],
+                [";", "q", "j", "k"],
+            ]),
+            _ => return Err(format!("Unknown keymap preset '{}'", name).into()),
+        };
+
+        Ok(keymap)
+    }
+
+    pub fn bind(&mut self, host_key: &str, key: u8) {
+        self.bindings.insert(host_key.to_string(), key & 0xF);
+    }
+    pub fn unbind(&mut self, host_key: &str) {
+        self.bindings.remove(host_key);
+    }
+    /** Removes every host key bound to `key` */
+    pub fn clear_key(&mut self, key: u8) {
+        self.bindings.retain(|_, bound| *bound != key & 0xF);
+    }
+
+    /** Looks up a host key, falling back to lower case so shifted letters still match */
+    pub fn lookup(&self, host_key: &str) -> Option<u8> {
+        self.bindings
+            .get(host_key)
+            .or_else(|| self.bindings.get(&host_key.to_lowercase()))
+            .copied()
+    }
+    pub fn host_keys(&self, key: u8) -> Vec<&str> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == key & 0xF)
+            .map(|(host_key, _)| host_key.as_str())
+            .collect()
+    }
+
+    /** Serializes as an object from hex digit to host keys, e.g. `{"0":["KeyX"],"1":["Digit1"]}` */
+    pub fn to_json(&self) -> String {
+        let by_key: BTreeMap<String, Vec<&str>> = (0..16)
+            .map(|key| (format!("{:X}", key), self.host_keys(key)))
+            .filter(|(_, host_keys)| !host_keys.is_empty())
+            .collect();
+
+        serde_json::to_string(&by_key).expect("Failed to serialize keymap!")
+    }
+    pub fn from_json(json: &str) -> Result<Keymap, Box<dyn std::error::Error>> {
+        let by_key: BTreeMap<String, Vec<String>> = serde_json::from_str(json)?;
+
+        let mut keymap = Keymap::empty();
+        for (key, host_keys) in by_key {
+            let key = match u8::from_str_radix(&key, 16) {
+                Ok(key) if key <= 0xF => key,
+                _ => return Err(format!("Invalid CHIP-8 key '{}' in keymap", key).into()),
+            };
+            for host_key in host_keys {
+                keymap.bind(&host_key, key);
+            }
+        }
+
+        Ok(keymap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Keymap;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_default() {
+        // Arrange
+        let keymap = Keymap::default();
+
+        // Act
+        let keys: Vec<Option<u8>> = ["Digit4", "KeyX", "KeyV", "Space"]
+            .iter()
+            .map(|host_key| keymap.lookup(host_key))
+            .collect();
+
+        // Assert
+        assert_eq!(keys, [Some(0xC), Some(0x0), Some(0xF), None]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_several_host_keys() {
+        // Arrange
+        let mut keymap = Keymap::default();
+
+        // Act
+        keymap.bind("ArrowUp", 0x5);
+
+        // Assert
+        assert_eq!(keymap.lookup("ArrowUp"), Some(0x5));
+        assert_eq!(keymap.lookup("KeyW"), Some(0x5));
+        assert_eq!(keymap.host_keys(0x5), ["ArrowUp", "KeyW"]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_preset() {
+        // Arrange
+        let azerty = Keymap::preset("azerty").unwrap();
+        let dvorak = Keymap::preset("Dvorak").unwrap();
+
+        // Act
+        let azerty_keys = (azerty.lookup("A"), azerty.lookup("é"), azerty.lookup("2"));
+        let dvorak_key = dvorak.lookup(",");
+
+        // Assert
+        assert_eq!(azerty_keys, (Some(0x4), Some(0x2), Some(0x2)));
+        assert_eq!(dvorak_key, Some(0x5));
+        assert!(Keymap::preset("colemak").is_err());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_json() {
+        // Arrange
+        let mut keymap = Keymap::empty();
+        keymap.bind("KeyX", 0x0);
+        keymap.bind("ArrowLeft", 0xA);
+        keymap.bind("KeyZ", 0xA);
+
+        // Act
+        let json = keymap.to_json();
+
+        // Assert
+        assert_eq!(json, r#"{"0":["KeyX"],"A":["ArrowLeft","KeyZ"]}"#);
+        assert_eq!(Keymap::from_json(&json).unwrap(), keymap);
+        assert!(Keymap::from_json(r#"{"G":["KeyG"]}"#).is_err());
+    }
+}
@@ -1,81 +1,41 @@
-mod components {
+pub mod components {
     pub mod keypad;
     pub mod memory;
     pub mod processor;
+    pub mod rng;
     pub mod screen;
+    pub mod stack;
 }
-pub mod opcodes;
-
-use crate::components::{memory::Memory, *};
-use array_init::array_init;
-use components::processor::Compatibility;
-use log::*;
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-#[derive(Debug)]
-pub struct Emulator {
-    processor: processor::Processor,
-    screen: screen::Screen,
-}
-
-#[wasm_bindgen]
-impl Emulator {
-    pub fn init(compatibility: Compatibility) -> Emulator {
-        Emulator {
-            processor: processor::Processor::init_compat(compatibility),
-            screen: screen::Screen::init(),
-        }
-    }
-    pub fn load_rom(&mut self, rom: Vec<u8>) {
-        self.processor.pc = Memory::ROM_BEGIN_INDEX;
-        self.processor.gfx = array_init(|_| 0);
-        self.processor.memory.clear();
-        self.processor.memory.load_fonts();
-        self.processor.memory.load_rom(rom);
-    }
-
-    pub fn cycle(&mut self) {
-        self.processor.cycle();
-    }
-    pub fn draw(&self) {
-        self.screen.update(&self.processor.gfx);
-    }
+#[cfg(feature = "web")]
+mod web {
+    pub mod audio;
+    pub mod canvas;
+    pub mod emulator;
+    pub mod keyboard;
 }
+pub mod asm;
+pub mod backend;
+pub mod disasm;
+pub mod emulator;
+pub mod error;
+pub mod keymap;
+pub mod movie;
+pub mod octo;
+pub mod opcodes;
+pub mod quirks;
+pub mod rewind;
+pub mod savestate;
 
-#[wasm_bindgen]
-pub fn init() {
-    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-    console_log::init_with_level(Level::Warn).expect("Failed initializing logger!");
-}
-
-#[wasm_bindgen]
-pub fn on_key_down(code: &str) {
-    let key = match code {
-        "Digit1" => 0x1,
-        "Digit2" => 0x2,
-        "Digit3" => 0x3,
-        "Digit4" => 0xC,
-        "KeyQ" => 0x4,
-        "KeyW" => 0x5,
-        "KeyE" => 0x6,
-        "KeyR" => 0xD,
-        "KeyA" => 0x7,
-        "KeyS" => 0x8,
-        "KeyD" => 0x9,
-        "KeyF" => 0xE,
-        "KeyZ" => 0xA,
-        "KeyX" => 0x0,
-        "KeyC" => 0xB,
-        "KeyV" => 0xF,
-        _ => 0x0,
-    };
-
-    if key != 0x0 {
-        keypad::INSTANCE.lock().unwrap().set_key(key);
-    }
-}
-#[wasm_bindgen]
-pub fn on_key_up() {
-    keypad::INSTANCE.lock().unwrap().unset_key();
-}
+pub use components::{
+    keypad::Keypad,
+    memory::Memory,
+    processor::{KeyTrigger, Processor, ProcessorState},
+    screen::{Resolution, Screen},
+    stack::{OverflowPolicy, Stack},
+};
+pub use emulator::{Emulator, EmulatorBuilder};
+pub use error::EmulatorError;
+pub use keymap::Keymap;
+pub use quirks::{Platform, Preset, Quirks};
+#[cfg(feature = "web")]
+pub use web::emulator::{init, WebEmulator};
@@ -0,0 +1,195 @@
+use crate::components::memory::Memory;
+use crate::components::processor::KeyTrigger;
+use crate::components::stack::{OverflowPolicy, Stack};
+use crate::quirks::Quirks;
+use crate::savestate::{self, crc32, Reader};
+use std::error::Error;
+
+/** Keypad state from a cycle on, as a `Keypad` bit mask */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputEvent {
+    /** Cycles run since the ROM was loaded when the keypad changed */
+    pub cycle: u64,
+    pub keypad: u16,
+}
+
+/** Recorded run: the settings and ROM it started from and every keypad change along the way */
+/** Playing it back with `Emulator::play_movie` reproduces the run exactly */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movie {
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub key_trigger: KeyTrigger,
+    pub clock_hz: u32,
+    /** Where the ROM was loaded and started */
+    pub load_address: u16,
+    /** Bytes of memory, 4 KB up to 64 KB */
+    pub memory_size: usize,
+    pub stack_depth: usize,
+    pub overflow: OverflowPolicy,
+    pub font_set: [u8; 80],
+    /** SCHIP's RPL user flags, which outlive the ROM and so are part of where the run started */
+    pub rpl: [u8; 16],
+    /** CRC-32 of the ROM, see `Movie::rom_hash` */
+    pub rom_hash: u32,
+    /** Cycles the run lasted */
+    pub length: u64,
+    pub inputs: Vec<InputEvent>,
+}
+impl Movie {
+    /** Identifies a movie file */
+    pub const MAGIC: [u8; 4] = *b"C8MV";
+    /** Bumped whenever the layout changes */
+    pub const VERSION: u16 = 6;
+
+    pub fn rom_hash(rom: &[u8]) -> u32 {
+        crc32(rom)
+    }
+
+    /** Frames the run lasted, at the recorded clock rate */
+    pub fn frames(&self) -> u64 {
+        self.length / (self.clock_hz / 60).max(1) as u64
+    }
+
+    /** Magic, version, settings, input count and inputs, then a CRC-32 of everything before it */
+    /** All numbers are little endian */
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(144 + self.inputs.len() * 10);
+        bytes.extend(Movie::MAGIC);
+        bytes.extend(Movie::VERSION.to_le_bytes());
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.push(self.quirks.to_bits());
+        bytes.push(savestate::encode_key_trigger(self.key_trigger));
+        bytes.extend(self.clock_hz.to_le_bytes());
+        bytes.extend(self.load_address.to_le_bytes());
+        bytes.extend((self.memory_size as u32).to_le_bytes());
+        bytes.push(self.stack_depth as u8);
+        bytes.push(savestate::encode_overflow(self.overflow));
+        bytes.extend(self.font_set);
+        bytes.extend(self.rpl);
+        bytes.extend(self.rom_hash.to_le_bytes());
+        bytes.extend(self.length.to_le_bytes());
+        bytes.extend((self.inputs.len() as u32).to_le_bytes());
+        for input in &self.inputs {
+            bytes.extend(input.cycle.to_le_bytes());
+            bytes.extend(input.keypad.to_le_bytes());
+        }
+        bytes.extend(crc32(&bytes).to_le_bytes());
+
+        bytes
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Movie, Box<dyn Error>> {
+        if bytes.len() < 10 || bytes[0..4] != Movie::MAGIC {
+            return Err("Not a movie".into());
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 4);
+        if crc32(body).to_le_bytes() != checksum {
+            return Err("Movie checksum mismatch".into());
+        }
+
+        let mut reader = Reader::new(&body[4..]);
+        let version = reader.u16()?;
+        if version != Movie::VERSION {
+            return Err(format!("Unsupported movie version {}", version).into());
+        }
+        let seed = reader.u64()?;
+        let quirks = match reader.u8()? {
+            bits if bits < 0x80 => Quirks::from_bits(bits),
+            other => return Err(format!("Invalid quirks {:#04X}", other).into()),
+        };
+        let key_trigger = savestate::decode_key_trigger(reader.u8()?)?;
+        let clock_hz = reader.u32()?;
+        let load_address = reader.u16()?;
+        let memory_size = reader.u32()? as usize;
+        if !(Memory::SIZE..=Memory::MAX_SIZE).contains(&memory_size) {
+            return Err(format!("Invalid memory size {}", memory_size).into());
+        }
+        let stack_depth = reader.u8()? as usize;
+        if stack_depth == 0 || stack_depth > Stack::MAX_DEPTH {
+            return Err(format!("Invalid stack depth {}", stack_depth).into());
+        }
+        let overflow = savestate::decode_overflow(reader.u8()?)?;
+        let font_set = reader.array::<80>()?;
+        let rpl = reader.array::<16>()?;
+        let rom_hash = reader.u32()?;
+        let length = reader.u64()?;
+        let count = reader.u32()?;
+        let mut inputs = Vec::new();
+        for _ in 0..count {
+            inputs.push(InputEvent {
+                cycle: reader.u64()?,
+                keypad: reader.u16()?,
+            });
+        }
+        if !reader.is_empty() {
+            return Err("Movie has trailing data".into());
+        }
+        if inputs.windows(2).any(|pair| pair[0].cycle > pair[1].cycle) {
+            return Err("Movie inputs are out of order".into());
+        }
+
+        Ok(Movie {
+            seed,
+            quirks,
+            key_trigger,
+            clock_hz,
+            load_address,
+            memory_size,
+            stack_depth,
+            overflow,
+            font_set,
+            rpl,
+            rom_hash,
+            length,
+            inputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InputEvent, Movie};
+    use crate::components::memory::Memory;
+    use crate::components::processor::KeyTrigger;
+    use crate::components::stack::{OverflowPolicy, Stack};
+    use crate::quirks::Quirks;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_bytes() {
+        // Arrange
+        let movie = Movie {
+            seed: 42,
+            quirks: Quirks::cosmac_vip(),
+            key_trigger: KeyTrigger::Release,
+            clock_hz: 600,
+            load_address: 0x600,
+            memory_size: Memory::MAX_SIZE,
+            stack_depth: Stack::VIP_DEPTH,
+            overflow: OverflowPolicy::Schip,
+            font_set: [0xAA; 80],
+            rpl: [0x55; 16],
+            rom_hash: Movie::rom_hash(&[0x12, 0x00]),
+            length: 1000,
+            inputs: vec![
+                InputEvent {
+                    cycle: 0,
+                    keypad: 0,
+                },
+                InputEvent {
+                    cycle: 120,
+                    keypad: 0b10,
+                },
+            ],
+        };
+
+        // Act
+        let mut bytes = movie.to_bytes();
+
+        // Assert
+        assert_eq!(Movie::from_bytes(&bytes).unwrap(), movie);
+        assert_eq!(movie.frames(), 100);
+        bytes[20] ^= 1;
+        assert!(Movie::from_bytes(&bytes).is_err());
+    }
+}
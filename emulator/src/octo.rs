@@ -0,0 +1,1527 @@
+use crate::asm::AsmError;
+use crate::components::memory::Memory;
+use std::collections::HashMap;
+use std::error::Error;
+
+/** A `:breakpoint`, where a debugger should pause before running the instruction at `address` */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub address: u16,
+    pub name: String,
+}
+
+/** How a `:monitor` shows memory */
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorFormat {
+    /** A hex dump of this many bytes */
+    Bytes(usize),
+    /** Octo's format string, e.g. `"%2i %2i"` */
+    Format(String),
+}
+
+/** A `:monitor`, memory a debugger keeps on display while the program runs */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    /** The address as it was written, usually a label */
+    pub name: String,
+    pub address: u16,
+    pub format: MonitorFormat,
+}
+
+/** Compiled `.8o` source: the ROM plus the debug metadata that doesn't end up in it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct OctoProgram {
+    pub rom: Vec<u8>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub monitors: Vec<Monitor>,
+}
+
+/** A file given to a frontend: a ROM image, or Octo source compiled for the load address */
+#[derive(Debug, Clone, PartialEq)]
+pub enum RomFile {
+    Rom(Vec<u8>),
+    Octo(OctoProgram),
+}
+impl RomFile {
+    /** Reads `path` and compiles it if it ends in `.8o`, with compile errors pointing into the file like a compiler's */
+    pub fn read(path: &str, address: u16) -> Result<RomFile, Box<dyn Error>> {
+        let bytes =
+            std::fs::read(path).map_err(|err| format!("Failed to read '{}': {}", path, err))?;
+        if !path.ends_with(".8o") {
+            return Ok(RomFile::Rom(bytes));
+        }
+        let source = String::from_utf8(bytes)?;
+        let program = compile_at(&source, address).map_err(|err| format!("{}:{}", path, err))?;
+
+        Ok(RomFile::Octo(program))
+    }
+    pub fn rom(&self) -> &[u8] {
+        match self {
+            RomFile::Rom(rom) => rom,
+            RomFile::Octo(program) => &program.rom,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+    column: usize,
+    /** Written in double quotes, which `text` leaves out */
+    string: bool,
+}
+
+/** Octo tokens are separated by whitespace, `#` starts a comment */
+fn tokenize(source: &str) -> Result<Vec<Token>, AsmError> {
+    let mut tokens = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let chars: Vec<char> = text.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+            if chars[i] == '#' {
+                break;
+            }
+
+            let start = i;
+            let string = chars[i] == '"';
+            if string {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(AsmError {
+                        line: index + 1,
+                        column: start + 1,
+                        message: "Missing closing '\"'".to_string(),
+                    });
+                }
+                i += 1;
+            } else {
+                while i < chars.len() && !chars[i].is_whitespace() {
+                    i += 1;
+                }
+            }
+
+            let text: String = match string {
+                true => chars[start + 1..i - 1].iter().collect(),
+                false => chars[start..i].iter().collect(),
+            };
+            tokens.push(Token {
+                text,
+                line: index + 1,
+                column: start + 1,
+                string,
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+/** `42`, `-1`, `0x2A` or `0b101010` */
+fn number(text: &str) -> Option<f64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()? as f64
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()? as f64
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+#[derive(Debug, Clone)]
+struct Macro {
+    args: Vec<String>,
+    body: Vec<Token>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FixupKind {
+    /** The NNN of an opcode */
+    Address,
+    /** A whole 16-bit word, for `i := long` and `:pointer` */
+    Word,
+    /** The NN of `v0 := NN` in `:unpack`, the nibble joined with the top of the address */
+    UnpackHigh(u8),
+    /** The NN of `v0 := NN` in `:unpack long`, the top byte of a 16-bit address */
+    UnpackLongHigh,
+    /** The NN of `v1 := NN` in `:unpack`, the bottom byte of the address */
+    UnpackLow,
+}
+
+/** A label used before it was defined, filled in once every label is known */
+#[derive(Debug)]
+struct Fixup {
+    address: usize,
+    kind: FixupKind,
+    token: Token,
+}
+
+#[derive(Debug)]
+enum Block {
+    /** `if … begin`, `jump` is the address of the jump over the block */
+    If {
+        token: Token,
+        jump: usize,
+        has_else: bool,
+    },
+    /** `loop`, `breaks` are the jumps out of it from `while` */
+    Loop {
+        token: Token,
+        start: usize,
+        breaks: Vec<usize>,
+    },
+}
+
+/** Deepest nesting of macro expansions allowed, to stop macros that expand into themselves */
+const MAX_MACRO_DEPTH: usize = 256;
+
+const CALC_UNARY: [&str; 14] = [
+    "-", "~", "!", "sin", "cos", "tan", "exp", "log", "abs", "sqrt", "sign", "ceil", "floor", "@",
+];
+const CALC_BINARY: [&str; 19] = [
+    "+", "-", "*", "/", "%", "&", "|", "^", "<<", ">>", "pow", "min", "max", "<", ">", "<=", ">=",
+    "==", "!=",
+];
+
+struct Compiler {
+    /** Tokens left to compile, the next one last */
+    tokens: Vec<Token>,
+    /** Where errors about a missing token point */
+    end: (usize, usize),
+    origin: usize,
+    rom: Vec<u8>,
+    /** Address of the next byte to emit */
+    here: usize,
+    /** Whether anything was emitted yet, see `emit_byte` */
+    started: bool,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, f64>,
+    aliases: HashMap<String, u8>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+    breakpoints: Vec<Breakpoint>,
+    /** The address and format tokens of each `:monitor`, resolved at the end so they can use any label */
+    monitors: Vec<(Token, Token)>,
+    /** For each macro expansion in progress, innermost last, how many tokens are left once its body is used up */
+    expanding: Vec<usize>,
+}
+impl Compiler {
+    fn error(token: &Token, message: String) -> AsmError {
+        AsmError {
+            line: token.line,
+            column: token.column,
+            message,
+        }
+    }
+    fn next(&mut self) -> Result<Token, AsmError> {
+        self.tokens.pop().ok_or(AsmError {
+            line: self.end.0,
+            column: self.end.1,
+            message: "Unexpected end of the source".to_string(),
+        })
+    }
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.last()
+    }
+    fn expect(&mut self, text: &str) -> Result<Token, AsmError> {
+        let token = self.next()?;
+        if token.text != text || token.string {
+            return Err(Compiler::error(
+                &token,
+                format!("Expected '{}', found '{}'", text, token.text),
+            ));
+        }
+        Ok(token)
+    }
+
+    /** Octo jumps from the start of the ROM to `main`, unless `main` is defined before any code */
+    fn start(&mut self, token: &Token) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        if !self.labels.contains_key("main") {
+            let main = Token {
+                text: "main".to_string(),
+                ..token.clone()
+            };
+            self.rom.extend([0x10, 0x00]);
+            self.fixups.push(Fixup {
+                address: self.origin,
+                kind: FixupKind::Address,
+                token: main,
+            });
+            self.here = self.here.max(self.origin + 2);
+        }
+    }
+    fn emit_byte(&mut self, byte: u8, token: &Token) -> Result<(), AsmError> {
+        self.start(token);
+        if self.here > u16::MAX as usize {
+            return Err(Compiler::error(
+                token,
+                "Program doesn't fit in 64 KB".to_string(),
+            ));
+        }
+
+        let index = self.here - self.origin;
+        if index >= self.rom.len() {
+            self.rom.resize(index + 1, 0);
+        }
+        self.rom[index] = byte;
+        self.here += 1;
+
+        Ok(())
+    }
+    fn emit(&mut self, word: u16, token: &Token) -> Result<(), AsmError> {
+        self.emit_byte((word >> 8) as u8, token)?;
+        self.emit_byte(word as u8, token)
+    }
+    /** Overwrites the NNN of the jump at `address` to lead to the current address */
+    fn patch_jump(&mut self, address: usize, token: &Token) -> Result<(), AsmError> {
+        if self.here > 0xFFF {
+            return Err(Compiler::error(
+                token,
+                "Jump target is past 0xFFF".to_string(),
+            ));
+        }
+        let index = address - self.origin;
+        self.rom[index] = 0x10 | (self.here >> 8) as u8;
+        self.rom[index + 1] = self.here as u8;
+
+        Ok(())
+    }
+
+    fn register(&self, token: &Token) -> Option<u8> {
+        if let Some(x) = self.aliases.get(&token.text) {
+            return Some(*x);
+        }
+        match token.text.as_bytes() {
+            [b'v' | b'V', digit] => (*digit as char).to_digit(16).map(|x| x as u8),
+            _ => None,
+        }
+    }
+    fn expect_register(&mut self) -> Result<u8, AsmError> {
+        let token = self.next()?;
+        self.register(&token).ok_or(Compiler::error(
+            &token,
+            format!("Expected a register, found '{}'", token.text),
+        ))
+    }
+
+    /** A number, constant or label that is already defined */
+    fn value(&self, token: &Token) -> Result<i64, AsmError> {
+        if let Some(value) = number(&token.text) {
+            return Ok(value.floor() as i64);
+        }
+        if let Some(value) = self.constants.get(&token.text) {
+            return Ok(value.floor() as i64);
+        }
+        if let Some(address) = self.labels.get(&token.text) {
+            return Ok(*address as i64);
+        }
+        Err(Compiler::error(
+            token,
+            format!("Undefined name '{}'", token.text),
+        ))
+    }
+    fn ranged(&mut self, min: i64, max: i64, what: &str) -> Result<u16, AsmError> {
+        let token = self.next()?;
+        let value = self.value(&token)?;
+        if value < min || value > max {
+            return Err(Compiler::error(
+                &token,
+                format!("{} doesn't fit in {}", value, what),
+            ));
+        }
+        Ok((value & max) as u16)
+    }
+    fn byte(&mut self) -> Result<u16, AsmError> {
+        self.ranged(-0x80, 0xFF, "a byte")
+    }
+    fn nibble(&mut self) -> Result<u16, AsmError> {
+        self.ranged(0, 0xF, "a nibble")
+    }
+    /** Emits `opcode` with the next token as its NNN, which may be a label defined further down */
+    fn emit_address(&mut self, opcode: u16, token: &Token) -> Result<(), AsmError> {
+        self.start(token);
+        let target = self.next()?;
+        let nnn = match self.value(&target) {
+            Ok(value) if (0..=0xFFF).contains(&value) => value as u16,
+            Ok(value) => {
+                return Err(Compiler::error(
+                    &target,
+                    format!("{:#X} doesn't fit in a 12-bit address", value),
+                ))
+            }
+            Err(_) if number(&target.text).is_none() && !target.string => {
+                self.fixups.push(Fixup {
+                    address: self.here,
+                    kind: FixupKind::Address,
+                    token: target,
+                });
+                0
+            }
+            Err(err) => return Err(err),
+        };
+        self.emit(opcode | nnn, token)
+    }
+    /** Emits the next token as a 16-bit word, which may be a label defined further down */
+    fn emit_word(&mut self, token: &Token) -> Result<(), AsmError> {
+        self.start(token);
+        let target = self.next()?;
+        let word = match self.value(&target) {
+            Ok(value) => value as u16,
+            Err(_) if number(&target.text).is_none() && !target.string => {
+                self.fixups.push(Fixup {
+                    address: self.here,
+                    kind: FixupKind::Word,
+                    token: target,
+                });
+                0
+            }
+            Err(err) => return Err(err),
+        };
+        self.emit(word, token)
+    }
+
+    /** Compiles a condition into the instructions that compute it, and the instruction that skips when it's false */
+    fn condition(&mut self) -> Result<(Vec<u16>, u16), AsmError> {
+        let x = self.expect_register()? as u16;
+        let op = self.next()?;
+        let rhs = match op.text.as_str() {
+            "key" => return Ok((vec![], 0xE0A1 | x << 8)),
+            "-key" => return Ok((vec![], 0xE09E | x << 8)),
+            "==" | "!=" | "<" | ">" | "<=" | ">=" => self.next()?,
+            _ => {
+                return Err(Compiler::error(
+                    &op,
+                    format!("Unknown comparison '{}'", op.text),
+                ))
+            }
+        };
+        let y = self.register(&rhs).map(|y| y as u16);
+        let n = match y {
+            Some(_) => 0,
+            None => {
+                self.tokens.push(rhs);
+                self.byte()?
+            }
+        };
+
+        // VF ends up 1 when VX >= the other side for >= and <, and when the other side >= VX for <= and >
+        let (prelude, skip) = match (op.text.as_str(), y) {
+            ("==", Some(y)) => (vec![], 0x9000 | x << 8 | y << 4),
+            ("==", None) => (vec![], 0x4000 | x << 8 | n),
+            ("!=", Some(y)) => (vec![], 0x5000 | x << 8 | y << 4),
+            ("!=", None) => (vec![], 0x3000 | x << 8 | n),
+            (">=" | "<", Some(y)) => (vec![0x8F00 | x << 4, 0x8F05 | y << 4], 0),
+            (">=" | "<", None) => (vec![0x6F00 | n, 0x8F07 | x << 4], 0),
+            (_, Some(y)) => (vec![0x8F00 | y << 4, 0x8F05 | x << 4], 0),
+            (_, None) => (vec![0x6F00 | n, 0x8F05 | x << 4], 0),
+        };
+        let skip = match op.text.as_str() {
+            ">=" | "<=" => 0x3F00,
+            "<" | ">" => 0x3F01,
+            _ => skip,
+        };
+
+        Ok((prelude, skip))
+    }
+    /** The skip instruction that skips in exactly the cases `skip` doesn't */
+    fn invert(skip: u16) -> u16 {
+        match skip >> 12 {
+            0x3 => 0x4000 | (skip & 0x0FFF),
+            0x4 => 0x3000 | (skip & 0x0FFF),
+            0x5 => 0x9000 | (skip & 0x0FFF),
+            0x9 => 0x5000 | (skip & 0x0FFF),
+            _ => skip ^ (0x9E ^ 0xA1),
+        }
+    }
+    /** A condition followed by a jump that's taken when it's false, for `begin` and `while`. Returns the jump's address */
+    fn condition_jump(&mut self, token: &Token) -> Result<usize, AsmError> {
+        let (prelude, skip) = self.condition()?;
+        for word in prelude {
+            self.emit(word, token)?;
+        }
+        self.emit(Compiler::invert(skip), token)?;
+        let jump = self.here;
+        self.emit(0x1000, token)?;
+
+        Ok(jump)
+    }
+
+    /** Tokens up to the `}` matching an already read `{` */
+    fn braced(&mut self, opening: &Token) -> Result<Vec<Token>, AsmError> {
+        let mut depth = 0;
+        let mut body = Vec::new();
+        loop {
+            let Some(token) = self.tokens.pop() else {
+                return Err(Compiler::error(opening, "Missing closing '}'".to_string()));
+            };
+            match token.text.as_str() {
+                "{" if !token.string => depth += 1,
+                "}" if !token.string && depth == 0 => return Ok(body),
+                "}" if !token.string => depth -= 1,
+                _ => {}
+            }
+            body.push(token);
+        }
+    }
+
+    /** Evaluates a `:calc` expression. Like Octo, operators all bind equally and apply right to left */
+    fn calc(
+        &self,
+        tokens: &[Token],
+        position: &mut usize,
+        opening: &Token,
+    ) -> Result<f64, AsmError> {
+        let left = self.calc_term(tokens, position, opening)?;
+        let Some(op) = tokens.get(*position) else {
+            return Ok(left);
+        };
+        if op.text == ")" {
+            return Ok(left);
+        }
+        if !CALC_BINARY.contains(&op.text.as_str()) {
+            return Err(Compiler::error(
+                op,
+                format!("Unknown operator '{}'", op.text),
+            ));
+        }
+        *position += 1;
+        let right = self.calc(tokens, position, opening)?;
+
+        let (a, b) = (left as i64, right as i64);
+        Ok(match op.text.as_str() {
+            "+" => left + right,
+            "-" => left - right,
+            "*" => left * right,
+            "/" | "%" if right == 0.0 => {
+                return Err(Compiler::error(op, "Division by zero".to_string()))
+            }
+            "/" => left / right,
+            "%" => left % right,
+            "&" => (a & b) as f64,
+            "|" => (a | b) as f64,
+            "^" => (a ^ b) as f64,
+            "<<" => a.wrapping_shl(b as u32) as f64,
+            ">>" => a.wrapping_shr(b as u32) as f64,
+            "pow" => left.powf(right),
+            "min" => left.min(right),
+            "max" => left.max(right),
+            "<" => (left < right) as u8 as f64,
+            ">" => (left > right) as u8 as f64,
+            "<=" => (left <= right) as u8 as f64,
+            ">=" => (left >= right) as u8 as f64,
+            "==" => (left == right) as u8 as f64,
+            _ => (left != right) as u8 as f64,
+        })
+    }
+    fn calc_term(
+        &self,
+        tokens: &[Token],
+        position: &mut usize,
+        opening: &Token,
+    ) -> Result<f64, AsmError> {
+        let Some(token) = tokens.get(*position) else {
+            return Err(Compiler::error(opening, "Expected a value".to_string()));
+        };
+        *position += 1;
+        if token.text == "(" {
+            let value = self.calc(tokens, position, opening)?;
+            match tokens.get(*position) {
+                Some(close) if close.text == ")" => *position += 1,
+                _ => return Err(Compiler::error(token, "Missing closing ')'".to_string())),
+            }
+            return Ok(value);
+        }
+        if CALC_UNARY.contains(&token.text.as_str()) {
+            let value = self.calc_term(tokens, position, opening)?;
+            return Ok(match token.text.as_str() {
+                "-" => -value,
+                "~" => !(value as i64) as f64,
+                "!" => (value == 0.0) as u8 as f64,
+                "sin" => value.sin(),
+                "cos" => value.cos(),
+                "tan" => value.tan(),
+                "exp" => value.exp(),
+                "log" => value.ln(),
+                "abs" => value.abs(),
+                "sqrt" => value.sqrt(),
+                "sign" => value.signum(),
+                "ceil" => value.ceil(),
+                "floor" => value.floor(),
+                // @ reads a byte of the ROM compiled so far
+                _ => {
+                    let index = (value as usize).wrapping_sub(self.origin);
+                    self.rom.get(index).copied().unwrap_or(0) as f64
+                }
+            });
+        }
+        match token.text.as_str() {
+            "HERE" => Ok(self.here as f64),
+            "PI" => Ok(std::f64::consts::PI),
+            "E" => Ok(std::f64::consts::E),
+            _ => match number(&token.text) {
+                Some(value) => Ok(value),
+                None => self
+                    .value(token)
+                    .map(|value| match self.constants.get(&token.text) {
+                        // Keep the fractions of other :calc results
+                        Some(constant) => *constant,
+                        None => value as f64,
+                    }),
+            },
+        }
+    }
+
+    fn define_label(&mut self, token: &Token, address: usize) -> Result<(), AsmError> {
+        if self.labels.contains_key(&token.text) || self.constants.contains_key(&token.text) {
+            return Err(Compiler::error(
+                token,
+                format!("'{}' is already defined", token.text),
+            ));
+        }
+        self.labels.insert(token.text.clone(), address as u16);
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), AsmError> {
+        let token = self.next()?;
+        if let Some(x) = self.register(&token) {
+            return self.assignment(&token, x as u16);
+        }
+
+        let word = match token.text.as_str() {
+            _ if token.string => {
+                return Err(Compiler::error(&token, "Unexpected string".to_string()))
+            }
+            "clear" => 0x00E0,
+            "return" | ";" => 0x00EE,
+            "scroll-down" => 0x00C0 | self.nibble()?,
+            "scroll-up" => 0x00D0 | self.nibble()?,
+            "scroll-right" => 0x00FB,
+            "scroll-left" => 0x00FC,
+            "exit" => 0x00FD,
+            "lores" => 0x00FE,
+            "hires" => 0x00FF,
+            "audio" => 0xF002,
+            "plane" => 0xF001 | self.nibble()? << 8,
+            "jump" => return self.emit_address(0x1000, &token),
+            "jump0" => return self.emit_address(0xB000, &token),
+            ":call" => return self.emit_address(0x2000, &token),
+            "native" => return self.emit_address(0x0000, &token),
+            "sprite" => {
+                let x = self.expect_register()? as u16;
+                let y = self.expect_register()? as u16;
+                0xD000 | x << 8 | y << 4 | self.nibble()?
+            }
+            "save" | "load" => {
+                let x = self.expect_register()? as u16;
+                let ranged = self.peek().is_some_and(|next| next.text == "-");
+                match (token.text.as_str(), ranged) {
+                    ("save", false) => 0xF055 | x << 8,
+                    (_, false) => 0xF065 | x << 8,
+                    (save_or_load, true) => {
+                        self.next()?;
+                        let y = self.expect_register()? as u16;
+                        let n = if save_or_load == "save" { 0x2 } else { 0x3 };
+                        0x5000 | x << 8 | y << 4 | n
+                    }
+                }
+            }
+            "saveflags" => 0xF075 | (self.expect_register()? as u16) << 8,
+            "loadflags" => 0xF085 | (self.expect_register()? as u16) << 8,
+            "bcd" => 0xF033 | (self.expect_register()? as u16) << 8,
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.expect_register()? as u16;
+                match token.text.as_str() {
+                    "delay" => 0xF015 | x << 8,
+                    "buzzer" => 0xF018 | x << 8,
+                    _ => 0xF03A | x << 8,
+                }
+            }
+            "i" => {
+                let op = self.next()?;
+                match op.text.as_str() {
+                    "+=" => 0xF01E | (self.expect_register()? as u16) << 8,
+                    ":=" => {
+                        let rhs = self.next()?;
+                        match rhs.text.as_str() {
+                            "hex" => 0xF029 | (self.expect_register()? as u16) << 8,
+                            "bighex" => 0xF030 | (self.expect_register()? as u16) << 8,
+                            "long" => {
+                                self.emit(0xF000, &token)?;
+                                return self.emit_word(&token);
+                            }
+                            _ => {
+                                self.tokens.push(rhs);
+                                return self.emit_address(0xA000, &token);
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err(Compiler::error(
+                            &op,
+                            format!("Unknown operator '{}'", op.text),
+                        ))
+                    }
+                }
+            }
+            "if" => {
+                let (prelude, skip) = self.condition()?;
+                let then = self.next()?;
+                match then.text.as_str() {
+                    "then" => {
+                        for word in prelude {
+                            self.emit(word, &token)?;
+                        }
+                        skip
+                    }
+                    "begin" => {
+                        for word in prelude {
+                            self.emit(word, &token)?;
+                        }
+                        self.emit(Compiler::invert(skip), &token)?;
+                        self.blocks.push(Block::If {
+                            token: token.clone(),
+                            jump: self.here,
+                            has_else: false,
+                        });
+                        0x1000
+                    }
+                    _ => {
+                        return Err(Compiler::error(
+                            &then,
+                            format!("Expected 'then' or 'begin', found '{}'", then.text),
+                        ))
+                    }
+                }
+            }
+            "else" => {
+                let Some(Block::If {
+                    jump,
+                    has_else: false,
+                    ..
+                }) = self.blocks.last()
+                else {
+                    return Err(Compiler::error(
+                        &token,
+                        "'else' without 'begin'".to_string(),
+                    ));
+                };
+                let skipped = *jump;
+                let end_jump = self.here;
+                self.emit(0x1000, &token)?;
+                self.patch_jump(skipped, &token)?;
+                if let Some(Block::If { jump, has_else, .. }) = self.blocks.last_mut() {
+                    *jump = end_jump;
+                    *has_else = true;
+                }
+                return Ok(());
+            }
+            "end" => {
+                let Some(Block::If { jump, .. }) = self.blocks.last() else {
+                    return Err(Compiler::error(&token, "'end' without 'begin'".to_string()));
+                };
+                let jump = *jump;
+                self.blocks.pop();
+                return self.patch_jump(jump, &token);
+            }
+            "loop" => {
+                self.start(&token);
+                self.blocks.push(Block::Loop {
+                    token: token.clone(),
+                    start: self.here,
+                    breaks: Vec::new(),
+                });
+                return Ok(());
+            }
+            "while" => {
+                let jump = self.condition_jump(&token)?;
+                let Some(Block::Loop { breaks, .. }) = self
+                    .blocks
+                    .iter_mut()
+                    .rev()
+                    .find(|block| matches!(block, Block::Loop { .. }))
+                else {
+                    return Err(Compiler::error(
+                        &token,
+                        "'while' outside a loop".to_string(),
+                    ));
+                };
+                breaks.push(jump);
+                return Ok(());
+            }
+            "again" => {
+                let Some(Block::Loop { start, .. }) = self.blocks.last() else {
+                    return Err(Compiler::error(
+                        &token,
+                        "'again' without 'loop'".to_string(),
+                    ));
+                };
+                let start = *start;
+                if start > 0xFFF {
+                    return Err(Compiler::error(
+                        &token,
+                        "Loop starts past 0xFFF".to_string(),
+                    ));
+                }
+                self.emit(0x1000 | start as u16, &token)?;
+                if let Some(Block::Loop { breaks, .. }) = self.blocks.pop() {
+                    for jump in breaks {
+                        self.patch_jump(jump, &token)?;
+                    }
+                }
+                return Ok(());
+            }
+            text if text.starts_with(':') => return self.directive(&token),
+            _ => {
+                if let Some(value) = number(&token.text) {
+                    let value = value.floor() as i64;
+                    if !(-0x80..=0xFF).contains(&value) {
+                        return Err(Compiler::error(
+                            &token,
+                            format!("{} doesn't fit in a byte", value),
+                        ));
+                    }
+                    return self.emit_byte(value as u8, &token);
+                }
+                if let Some(value) = self.constants.get(&token.text) {
+                    return self.emit_byte(value.floor() as i64 as u8, &token);
+                }
+                if let Some(definition) = self.macros.get(&token.text).cloned() {
+                    return self.expand(&token, definition);
+                }
+                // Any other name calls the subroutine with that label
+                self.tokens.push(token.clone());
+                return self.emit_address(0x2000, &token);
+            }
+        };
+
+        self.emit(word, &token)
+    }
+
+    fn assignment(&mut self, token: &Token, x: u16) -> Result<(), AsmError> {
+        let op = self.next()?;
+        let rhs = self.next()?;
+        let y = self.register(&rhs).map(|y| y as u16);
+        let word = match (op.text.as_str(), y) {
+            (":=", Some(y)) => 0x8000 | x << 8 | y << 4,
+            ("|=", Some(y)) => 0x8001 | x << 8 | y << 4,
+            ("&=", Some(y)) => 0x8002 | x << 8 | y << 4,
+            ("^=", Some(y)) => 0x8003 | x << 8 | y << 4,
+            ("+=", Some(y)) => 0x8004 | x << 8 | y << 4,
+            ("-=", Some(y)) => 0x8005 | x << 8 | y << 4,
+            (">>=", Some(y)) => 0x8006 | x << 8 | y << 4,
+            ("=-", Some(y)) => 0x8007 | x << 8 | y << 4,
+            ("<<=", Some(y)) => 0x800E | x << 8 | y << 4,
+            (":=", None) => match rhs.text.as_str() {
+                "random" => 0xC000 | x << 8 | self.byte()?,
+                "delay" => 0xF007 | x << 8,
+                "key" => 0xF00A | x << 8,
+                _ => {
+                    self.tokens.push(rhs);
+                    0x6000 | x << 8 | self.byte()?
+                }
+            },
+            ("+=", None) => {
+                self.tokens.push(rhs);
+                0x7000 | x << 8 | self.byte()?
+            }
+            // There's no subtraction of a constant, so this adds its negative
+            ("-=", None) => {
+                self.tokens.push(rhs);
+                0x7000 | x << 8 | (self.byte()?.wrapping_neg() & 0xFF)
+            }
+            _ => {
+                return Err(Compiler::error(
+                    &op,
+                    format!("Unknown operator '{}' for '{}'", op.text, rhs.text),
+                ))
+            }
+        };
+
+        self.emit(word, token)
+    }
+
+    fn directive(&mut self, token: &Token) -> Result<(), AsmError> {
+        match token.text.as_str() {
+            ":" => {
+                let name = self.next()?;
+                if name.text != "main" {
+                    self.start(token);
+                }
+                self.define_label(&name, self.here)
+            }
+            ":next" => {
+                self.start(token);
+                let name = self.next()?;
+                self.define_label(&name, self.here + 1)
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.next()?;
+                let value = self.value(&value)? as f64;
+                self.constants.insert(name.text, value);
+                Ok(())
+            }
+            ":calc" => {
+                let name = self.next()?;
+                let opening = self.expect("{")?;
+                let body = self.braced(&opening)?;
+                let mut position = 0;
+                let value = self.calc(&body, &mut position, &opening)?;
+                if let Some(extra) = body.get(position) {
+                    return Err(Compiler::error(
+                        extra,
+                        format!("Unexpected '{}'", extra.text),
+                    ));
+                }
+                self.constants.insert(name.text, value);
+                Ok(())
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let x = self.expect_register()?;
+                self.aliases.insert(name.text, x);
+                Ok(())
+            }
+            ":unpack" => {
+                let nibble = self.next()?;
+                let (nibble, long) = match nibble.text.as_str() {
+                    "long" => (0, true),
+                    _ => match self.value(&nibble)? {
+                        value @ 0..=0xF => (value as u8, false),
+                        _ => return Err(Compiler::error(&nibble, "Expected a nibble".to_string())),
+                    },
+                };
+                self.start(token);
+                let target = self.next()?;
+                let address = match self.value(&target) {
+                    Ok(address) if long || address <= 0xFFF => address as u16,
+                    Ok(_) => {
+                        return Err(Compiler::error(
+                            &target,
+                            "Expected a 12-bit address, use :unpack long".to_string(),
+                        ))
+                    }
+                    Err(_) if number(&target.text).is_none() => {
+                        self.fixups.push(Fixup {
+                            address: self.here,
+                            kind: match long {
+                                true => FixupKind::UnpackLongHigh,
+                                false => FixupKind::UnpackHigh(nibble),
+                            },
+                            token: target.clone(),
+                        });
+                        self.fixups.push(Fixup {
+                            address: self.here + 2,
+                            kind: FixupKind::UnpackLow,
+                            token: target,
+                        });
+                        0
+                    }
+                    Err(err) => return Err(err),
+                };
+                let high = ((nibble as u16) << 4 | address >> 8) & 0xFF;
+                self.emit(0x6000 | high, token)?;
+                self.emit(0x6100 | (address & 0xFF), token)
+            }
+            ":org" => {
+                let address = self.next()?;
+                match self.value(&address)? {
+                    value if value as usize >= self.origin && value <= 0xFFFF => {
+                        self.here = value as usize;
+                        Ok(())
+                    }
+                    _ => Err(Compiler::error(
+                        &address,
+                        format!("Expected an address from {:#X} on", self.origin),
+                    )),
+                }
+            }
+            ":byte" => {
+                let value = match self.peek().is_some_and(|next| next.text == "{") {
+                    true => {
+                        let opening = self.next()?;
+                        let body = self.braced(&opening)?;
+                        self.calc(&body, &mut 0, &opening)?.floor() as i64
+                    }
+                    false => self.byte()? as i64,
+                };
+                self.emit_byte(value as u8, token)
+            }
+            ":pointer" => self.emit_word(token),
+            ":macro" => {
+                let name = self.next()?;
+                let mut args = Vec::new();
+                loop {
+                    let arg = self.next()?;
+                    if arg.text == "{" {
+                        let body = self.braced(&arg)?;
+                        self.macros.insert(name.text, Macro { args, body });
+                        return Ok(());
+                    }
+                    args.push(arg.text);
+                }
+            }
+            ":breakpoint" => {
+                self.start(token);
+                let name = self.next()?;
+                self.breakpoints.push(Breakpoint {
+                    address: self.here as u16,
+                    name: name.text,
+                });
+                Ok(())
+            }
+            ":monitor" => {
+                let address = self.next()?;
+                let format = self.next()?;
+                self.monitors.push((address, format));
+                Ok(())
+            }
+            ":assert" => {
+                let message = match self.peek() {
+                    Some(next) if next.string => self.next()?.text,
+                    _ => "Assertion failed".to_string(),
+                };
+                let opening = self.expect("{")?;
+                let body = self.braced(&opening)?;
+                match self.calc(&body, &mut 0, &opening)? {
+                    0.0 => Err(Compiler::error(token, message)),
+                    _ => Ok(()),
+                }
+            }
+            _ => Err(Compiler::error(
+                token,
+                format!("Unsupported directive '{}'", token.text),
+            )),
+        }
+    }
+
+    /** Puts the macro body in front of the remaining tokens, with each argument replaced */
+    fn expand(&mut self, token: &Token, definition: Macro) -> Result<(), AsmError> {
+        // A macro used as the last token of a body is still nested in it, hence `<`
+        let left = self.tokens.len();
+        while self.expanding.last().is_some_and(|&end| left < end) {
+            self.expanding.pop();
+        }
+        if self.expanding.len() >= MAX_MACRO_DEPTH {
+            return Err(Compiler::error(
+                token,
+                format!("Macro '{}' expands without end", token.text),
+            ));
+        }
+
+        let mut values = HashMap::new();
+        for arg in &definition.args {
+            values.insert(arg.clone(), self.next()?);
+        }
+        self.expanding.push(self.tokens.len());
+        for body_token in definition.body.into_iter().rev() {
+            let substituted = match body_token.string {
+                true => None,
+                false => values.get(&body_token.text).cloned(),
+            };
+            self.tokens.push(substituted.unwrap_or(body_token));
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<OctoProgram, AsmError> {
+        if let Some(block) = self.blocks.last() {
+            return Err(match block {
+                Block::If { token, .. } => {
+                    Compiler::error(token, "'begin' without 'end'".to_string())
+                }
+                Block::Loop { token, .. } => {
+                    Compiler::error(token, "'loop' without 'again'".to_string())
+                }
+            });
+        }
+
+        for fixup in &self.fixups {
+            let Some(address) = self.labels.get(&fixup.token.text).copied() else {
+                return Err(Compiler::error(
+                    &fixup.token,
+                    format!("Undefined name '{}'", fixup.token.text),
+                ));
+            };
+            let index = fixup.address - self.origin;
+            match fixup.kind {
+                FixupKind::Address | FixupKind::UnpackHigh(_) if address > 0xFFF => {
+                    return Err(Compiler::error(
+                        &fixup.token,
+                        format!("'{}' is past 0xFFF", fixup.token.text),
+                    ))
+                }
+                FixupKind::Address => {
+                    self.rom[index] |= (address >> 8) as u8;
+                    self.rom[index + 1] = address as u8;
+                }
+                FixupKind::Word => {
+                    self.rom[index..index + 2].copy_from_slice(&address.to_be_bytes());
+                }
+                FixupKind::UnpackHigh(nibble) => {
+                    self.rom[index + 1] = nibble << 4 | (address >> 8) as u8;
+                }
+                FixupKind::UnpackLongHigh => self.rom[index + 1] = (address >> 8) as u8,
+                FixupKind::UnpackLow => self.rom[index + 1] = address as u8,
+            }
+        }
+
+        let mut monitors = Vec::new();
+        for (address, format) in &self.monitors {
+            let value = self.value(address)?;
+            monitors.push(Monitor {
+                name: address.text.clone(),
+                address: value as u16,
+                format: match format.string {
+                    true => MonitorFormat::Format(format.text.clone()),
+                    false => MonitorFormat::Bytes(self.value(format)?.max(0) as usize),
+                },
+            });
+        }
+
+        Ok(OctoProgram {
+            rom: self.rom,
+            breakpoints: self.breakpoints,
+            monitors,
+        })
+    }
+}
+
+/** Compiles Octo source for a ROM loaded at `Memory::ROM_BEGIN_INDEX`, see `compile_at` */
+pub fn compile(source: &str) -> Result<OctoProgram, AsmError> {
+    compile_at(source, Memory::ROM_BEGIN_INDEX)
+}
+
+/** Compiles Octo (`.8o`) source into a ROM loaded at `address` */
+/** Covers the instructions of CHIP-8, SUPER-CHIP and XO-CHIP, `: label`, `:const`, `:alias`, `:macro`, `:calc`, `:byte`, */
+/** `:pointer`, `:unpack`, `:next`, `:org`, `:assert`, `if … then`, `if … begin … else … end`, `loop … while … again`, */
+/** and the `:breakpoint` and `:monitor` debug metadata. `:stringmode` isn't supported */
+pub fn compile_at(source: &str, address: u16) -> Result<OctoProgram, AsmError> {
+    let mut tokens = tokenize(source)?;
+    let end = match tokens.last() {
+        Some(token) => (token.line, token.column + token.text.chars().count()),
+        None => (1, 1),
+    };
+    tokens.reverse();
+
+    let mut compiler = Compiler {
+        tokens,
+        end,
+        origin: address as usize,
+        rom: Vec::new(),
+        here: address as usize,
+        started: false,
+        labels: HashMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        macros: HashMap::new(),
+        fixups: Vec::new(),
+        blocks: Vec::new(),
+        breakpoints: Vec::new(),
+        monitors: Vec::new(),
+        expanding: Vec::new(),
+    };
+    while !compiler.tokens.is_empty() {
+        compiler.statement()?;
+    }
+
+    compiler.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, compile_at, Breakpoint, Monitor, MonitorFormat};
+    use crate::asm::AsmError;
+    use crate::disasm::{Instruction, Syntax};
+    use crate::emulator::Emulator;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn run(source: &str, cycles: usize) -> Emulator {
+        let mut emulator = Emulator::builder().build();
+        emulator.load_octo_source(source).unwrap();
+        for _ in 0..cycles {
+            emulator.cycle().unwrap();
+        }
+        emulator
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_compile() {
+        // Arrange
+        let source = "
+            :const COUNT 3       # loops before stopping
+            :alias counter v0
+            : main
+                counter := 0
+                add-one
+                if counter != COUNT then jump main-loop
+                i := data
+                load v2
+            : end
+                jump end
+            : main-loop
+                jump 0x202
+            : add-one
+                counter += 1
+                return
+            : data
+                0x41 0x42 -1
+        ";
+
+        // Act
+        let rom = compile(source).unwrap().rom;
+
+        // Assert
+        assert_eq!(
+            rom,
+            vec![
+                0x60, 0x00, 0x22, 0x10, 0x30, 0x03, 0x12, 0x0E, 0xA2, 0x14, 0xF2, 0x65, 0x12, 0x0C,
+                0x12, 0x02, 0x70, 0x01, 0x00, 0xEE, 0x41, 0x42, 0xFF,
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_jump_to_main() {
+        // Arrange
+        let source = "
+            : double
+                v0 += v0
+                ;
+            : main
+                v0 := 3
+                double
+            : end
+                jump end
+        ";
+
+        // Act
+        let emulator = run(source, 10);
+
+        // Assert
+        assert_eq!(
+            emulator.memory()[0x200..0x20C],
+            [0x12, 0x06, 0x80, 0x04, 0x00, 0xEE, 0x60, 0x03, 0x22, 0x02, 0x12, 0x0A]
+        );
+        assert_eq!(emulator.v()[0], 6);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_round_trip() {
+        for opcode in 0..=u16::MAX {
+            // Arrange
+            let instruction = Instruction::decode(opcode);
+            if matches!(instruction, Instruction::Unknown { .. }) {
+                continue;
+            }
+            let source = format!(": main {}", instruction.mnemonic(Syntax::Octo));
+
+            // Act
+            let rom = compile(&source).unwrap().rom;
+
+            // Assert
+            assert_eq!(rom.len(), instruction.size() as usize, "{}", source);
+            assert_eq!(
+                Instruction::decode(u16::from_be_bytes([rom[0], rom[1]])),
+                instruction,
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_macro() {
+        // Arrange
+        let source = "
+            :macro set-pair A B X { A := X B := X }
+            : main
+                set-pair v1 v2 7
+                set-pair v3 v4 0x10
+        ";
+
+        // Act
+        let rom = compile(source).unwrap().rom;
+
+        // Assert
+        assert_eq!(rom, vec![0x61, 0x07, 0x62, 0x07, 0x63, 0x10, 0x64, 0x10]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_calc() {
+        // Arrange
+        let source = "
+            :const WIDTH 64
+            :calc CENTER { ( WIDTH / 2 ) - 4 }
+            :calc RIGHT_TO_LEFT { 2 * 3 + 1 }
+            :calc ROUNDED { floor 2.5 + 0.75 }
+            : main
+                v0 := CENTER
+                v1 := RIGHT_TO_LEFT
+                v2 := ROUNDED
+                :byte { HERE & 0xFF }
+                :byte { ( 1 << 4 ) | 3 }
+        ";
+
+        // Act
+        let rom = compile(source).unwrap().rom;
+
+        // Assert
+        assert_eq!(rom, vec![0x60, 28, 0x61, 8, 0x62, 2, 0x06, 0x13]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_loop() {
+        // Arrange
+        let source = "
+            : main
+                v0 := 0
+                v1 := 0
+                loop
+                    v0 += 1
+                    while v0 != 10
+                    v1 += 2
+                again
+            : end
+                jump end
+        ";
+
+        // Act
+        let emulator = run(source, 100);
+
+        // Assert
+        assert_eq!(emulator.v()[0..2], [10, 18]);
+        assert_eq!(emulator.pc(), 0x20E);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_if() {
+        // Arrange
+        let source = "
+            : main
+                v0 := 5
+                if v0 == 5 then v1 := 1
+                if v0 == 6 then v2 := 1
+                if v0 != 5 begin
+                    v3 := 1
+                else
+                    v3 := 2
+                end
+                if v0 == v0 begin v4 := 1 end
+            : end
+                jump end
+        ";
+
+        // Act
+        let emulator = run(source, 30);
+
+        // Assert
+        assert_eq!(emulator.v()[1..5], [1, 0, 2, 1]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_comparisons() {
+        for (a, op, b) in [
+            (3, "<", 5),
+            (5, "<", 5),
+            (5, ">", 3),
+            (3, ">", 3),
+            (5, ">=", 5),
+            (4, ">=", 5),
+            (5, "<=", 5),
+            (6, "<=", 5),
+        ] {
+            for register in [false, true] {
+                // Arrange
+                let rhs = if register {
+                    "v2".to_string()
+                } else {
+                    b.to_string()
+                };
+                let source = format!(
+                    ": main v1 := {} v2 := {} v0 := 0 if v1 {} {} then v0 := 1 : end jump end",
+                    a, b, op, rhs
+                );
+                let expected = match op {
+                    "<" => a < b,
+                    ">" => a > b,
+                    ">=" => a >= b,
+                    _ => a <= b,
+                };
+
+                // Act
+                let emulator = run(&source, 20);
+
+                // Assert
+                assert_eq!(emulator.v()[0] == 1, expected, "{}", source);
+            }
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_forward_references() {
+        // Arrange
+        let source = "
+            : main
+                i := long data
+                :unpack 0xA data
+                :pointer data
+            : data
+        ";
+
+        // Act
+        let rom = compile_at(source, 0x600).unwrap().rom;
+
+        // Assert
+        assert_eq!(
+            rom,
+            vec![0xF0, 0x00, 0x06, 0x0A, 0x60, 0xA6, 0x61, 0x0A, 0x06, 0x0A]
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_forward_unpack_long() {
+        // Arrange
+        let source = "
+            : main
+                :unpack long far
+            :org 0x1200
+            : far
+        ";
+
+        // Act
+        let rom = compile(source).unwrap().rom;
+
+        // Assert
+        assert_eq!(rom[..4], [0x60, 0x12, 0x61, 0x00]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_macro_uses() {
+        // Arrange
+        let source = format!(
+            ":macro one {{ :byte 1 }}\n: main\n{}",
+            "one ".repeat(20_000)
+        );
+
+        // Act
+        let rom = compile(&source).unwrap().rom;
+
+        // Assert
+        assert_eq!(rom.len(), 20_000);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_debug_metadata() {
+        // Arrange
+        let source = "
+            : main
+                v0 := 1
+                :breakpoint check-v0
+                v1 := 2
+                :monitor score 3
+                :monitor 0x300 \"%2i\"
+            : score
+        ";
+
+        // Act
+        let mut emulator = Emulator::builder().build();
+        emulator.load_octo_source(source).unwrap();
+        let before = emulator.breakpoint().cloned();
+        emulator.cycle().unwrap();
+        let after = emulator.breakpoint().cloned();
+
+        // Assert
+        let breakpoint = Breakpoint {
+            address: 0x202,
+            name: "check-v0".to_string(),
+        };
+        assert_eq!(emulator.breakpoints(), std::slice::from_ref(&breakpoint));
+        assert_eq!((before, after), (None, Some(breakpoint)));
+        assert_eq!(
+            emulator.monitors(),
+            [
+                Monitor {
+                    name: "score".to_string(),
+                    address: 0x204,
+                    format: MonitorFormat::Bytes(3),
+                },
+                Monitor {
+                    name: "0x300".to_string(),
+                    address: 0x300,
+                    format: MonitorFormat::Format("%2i".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_errors() {
+        for (source, line, column, message) in [
+            (": main\n  v0 := 256", 2, 9, "256 doesn't fit in a byte"),
+            (": main\n  jump nowhere", 2, 8, "Undefined name 'nowhere'"),
+            (": main\n  v0 <- 1", 2, 6, "Unknown operator '<-' for '1'"),
+            (": main : main", 1, 10, "'main' is already defined"),
+            (": main\n  loop v0 += 1", 2, 3, "'loop' without 'again'"),
+            (
+                ": main\n  if v0 == 1 v1 := 2",
+                2,
+                14,
+                "Expected 'then' or 'begin', found 'v1'",
+            ),
+            (":calc X { 1 + }", 1, 9, "Expected a value"),
+            (": main\n  v0 :=", 2, 8, "Unexpected end of the source"),
+            (
+                ":macro loop-forever { loop-forever }\n: main loop-forever",
+                1,
+                23,
+                "Macro 'loop-forever' expands without end",
+            ),
+            (
+                ": main :stringmode",
+                1,
+                8,
+                "Unsupported directive ':stringmode'",
+            ),
+            ("v0 := 1", 1, 1, "Undefined name 'main'"),
+        ] {
+            // Act
+            let result = compile(source);
+
+            // Assert
+            assert_eq!(
+                result,
+                Err(AsmError {
+                    line,
+                    column,
+                    message: message.to_string(),
+                }),
+                "{}",
+                source
+            );
+        }
+    }
+}
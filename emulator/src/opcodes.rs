@@ -1,18 +1,25 @@
 use crate::components::memory::Memory;
-
-use super::keypad;
-use super::processor::{Compatibility, Processor};
-use super::screen::Screen;
-use array_init::array_init;
+use crate::components::processor::{Processor, ProcessorState};
+use crate::components::screen::Resolution;
+use crate::error::EmulatorError;
 use log::*;
 
 pub struct OpCode00E0;
 pub struct OpCode00EE;
+pub struct OpCode00CN;
+pub struct OpCode00DN;
+pub struct OpCode00FB;
+pub struct OpCode00FC;
+pub struct OpCode00FD;
+pub struct OpCode00FE;
+pub struct OpCode00FF;
 pub struct OpCode1NNN;
 pub struct OpCode2NNN;
 pub struct OpCode3XNN;
 pub struct OpCode4XNN;
 pub struct OpCode5XY0;
+pub struct OpCode5XY2;
+pub struct OpCode5XY3;
 pub struct OpCode6XNN;
 pub struct OpCode7XNN;
 pub struct OpCode8XY0;
@@ -32,324 +39,594 @@ pub struct OpCodeCXNN;
 pub struct OpCodeDXYN;
 pub struct OpCodeEX9E;
 pub struct OpCodeEXA1;
+pub struct OpCodeF000;
+pub struct OpCodeFN01;
+pub struct OpCodeF002;
+pub struct OpCodeFX3A;
 pub struct OpCodeFX07;
 pub struct OpCodeFX0A;
 pub struct OpCodeFX15;
 pub struct OpCodeFX18;
 pub struct OpCodeFX29;
+pub struct OpCodeFX30;
 pub struct OpCodeFX33;
 pub struct OpCodeFX1E;
 pub struct OpCodeFX55;
 pub struct OpCodeFX65;
+pub struct OpCodeFX75;
+pub struct OpCodeFX85;
 
 pub trait OpCode {
-    fn execute(processor: &mut Processor, data: &[u16]);
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError>;
+}
+
+/** Fails with `MemoryOutOfBounds` unless `len` bytes from `address` are all in memory */
+fn check_memory(processor: &Processor, address: usize, len: usize) -> Result<(), EmulatorError> {
+    let size = processor.memory.data.len();
+    if address + len > size {
+        return Err(EmulatorError::MemoryOutOfBounds {
+            pc: processor.instruction_pc,
+            opcode: processor.instruction,
+            address: address.max(size),
+        });
+    }
+
+    Ok(())
+}
+
+/** Skips the next instruction, which is 4 bytes long if it's XO-CHIP's F000 NNNN */
+fn skip_next(processor: &mut Processor) {
+    let pc = processor.pc as usize;
+    let long = processor.memory.data.get(pc..pc + 2) == Some(&[0xF0, 0x00]);
+    processor.pc = processor.pc.wrapping_add(if long { 4 } else { 2 });
+}
+
+/** Moves the selected bitplanes `dx` pixels right and `dy` down, what comes in from past the edges is dark */
+fn scroll(processor: &mut Processor, dx: isize, dy: isize) {
+    let width = processor.width() as isize;
+    let height = processor.height() as isize;
+    let planes = processor.planes;
+    let source = processor.gfx;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (from_x, from_y) = (x - dx, y - dy);
+            let moved = if (0..width).contains(&from_x) && (0..height).contains(&from_y) {
+                source[(from_y * width + from_x) as usize] & planes
+            } else {
+                0
+            };
+            let i = (y * width + x) as usize;
+            processor.gfx[i] = processor.gfx[i] & !planes | moved;
+        }
+    }
 }
 
 impl OpCode for OpCode00E0 {
-    fn execute(processor: &mut Processor, _: &[u16]) {
-        processor.gfx = array_init(|_| 0);
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        let planes = processor.planes;
+        processor.gfx.iter_mut().for_each(|pixel| *pixel &= !planes);
+
+        Ok(())
     }
 }
 impl OpCode for OpCode00EE {
-    fn execute(processor: &mut Processor, _: &[u16]) {
-        let return_address = processor.stack.pop().expect("Stack is empty!");
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        let Some(return_address) = processor.stack.pop() else {
+            return Err(EmulatorError::StackUnderflow {
+                pc: processor.instruction_pc,
+                opcode: processor.instruction,
+            });
+        };
         processor.pc = return_address;
+
+        Ok(())
+    }
+}
+impl OpCode for OpCode00CN {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        scroll(processor, 0, data[0] as isize);
+        Ok(())
+    }
+}
+impl OpCode for OpCode00DN {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        scroll(processor, 0, -(data[0] as isize));
+        Ok(())
+    }
+}
+impl OpCode for OpCode00FB {
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        scroll(processor, 4, 0);
+        Ok(())
+    }
+}
+impl OpCode for OpCode00FC {
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        scroll(processor, -4, 0);
+        Ok(())
+    }
+}
+impl OpCode for OpCode00FD {
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        processor.state = ProcessorState::Exited;
+        Ok(())
+    }
+}
+impl OpCode for OpCode00FE {
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        processor.set_resolution(Resolution::Low);
+        Ok(())
+    }
+}
+impl OpCode for OpCode00FF {
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        processor.set_resolution(Resolution::High);
+        Ok(())
     }
 }
 impl OpCode for OpCode1NNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         processor.pc = data[0];
+        Ok(())
     }
 }
 impl OpCode for OpCode2NNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let nnn = data[0];
-        processor.stack.push(processor.pc);
+        if !processor.stack.push(processor.pc) {
+            return Err(EmulatorError::StackOverflow {
+                pc: processor.instruction_pc,
+                opcode: processor.instruction,
+            });
+        }
         processor.pc = nnn;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode3XNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let nn = data[1] as u8;
 
         if processor.v[x] == nn {
-            processor.pc += 2;
+            skip_next(processor);
         }
+
+        Ok(())
     }
 }
 impl OpCode for OpCode4XNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let nn = data[1] as u8;
 
         if processor.v[x] != nn {
-            processor.pc += 2;
+            skip_next(processor);
         }
+
+        Ok(())
     }
 }
 impl OpCode for OpCode5XY0 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
 
         if processor.v[x] == processor.v[y] {
-            processor.pc += 2;
+            skip_next(processor);
+        }
+
+        Ok(())
+    }
+}
+impl OpCode for OpCode5XY2 {
+    /** Stores VX through VY at I, in reverse if X > Y. I is left as it is */
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        let x = data[0] as usize;
+        let y = data[1] as usize;
+        let len = x.abs_diff(y) + 1;
+
+        check_memory(processor, processor.i as usize, len)?;
+        for offset in 0..len {
+            let register = if x <= y { x + offset } else { x - offset };
+            processor.memory.data[processor.i as usize + offset] = processor.v[register];
+        }
+
+        Ok(())
+    }
+}
+impl OpCode for OpCode5XY3 {
+    /** Loads VX through VY from I, in reverse if X > Y. I is left as it is */
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        let x = data[0] as usize;
+        let y = data[1] as usize;
+        let len = x.abs_diff(y) + 1;
+
+        check_memory(processor, processor.i as usize, len)?;
+        for offset in 0..len {
+            let register = if x <= y { x + offset } else { x - offset };
+            processor.v[register] = processor.memory.data[processor.i as usize + offset];
         }
+
+        Ok(())
     }
 }
 impl OpCode for OpCode6XNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let nn = data[1] as u8;
         processor.v[x] = nn;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode7XNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let nn = data[1] as u8;
-        processor.v[x] = processor.v[x].wrapping_add(nn)
+        processor.v[x] = processor.v[x].wrapping_add(nn);
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY0 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         processor.v[x] = processor.v[y];
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY1 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         processor.v[x] |= processor.v[y];
+        if processor.quirks.vf_reset {
+            processor.v[0xF] = 0;
+        }
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY2 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         processor.v[x] &= processor.v[y];
+        if processor.quirks.vf_reset {
+            processor.v[0xF] = 0;
+        }
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY3 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         processor.v[x] ^= processor.v[y];
+        if processor.quirks.vf_reset {
+            processor.v[0xF] = 0;
+        }
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY4 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         let (result, overflow) = processor.v[x].overflowing_add(processor.v[y]);
 
         processor.v[x] = result;
         processor.v[0xF] = overflow as u8;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY5 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         let (result, overflow) = processor.v[x].overflowing_sub(processor.v[y]);
 
         processor.v[x] = result;
         processor.v[0xF] = !overflow as u8;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY6 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
 
-        if processor.compatibility == Compatibility::Original {
+        if !processor.quirks.shift {
             processor.v[x] = processor.v[y];
         }
         processor.v[0xF] = processor.v[x] & 0x1;
         processor.v[x] >>= 1;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XY7 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         let (result, overflow) = processor.v[y].overflowing_sub(processor.v[x]);
 
         processor.v[x] = result;
         processor.v[0xF] = !overflow as u8;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode8XYE {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
 
-        if processor.compatibility == Compatibility::Original {
+        if !processor.quirks.shift {
             processor.v[x] = processor.v[y];
         }
         processor.v[0xF] = (processor.v[x] & 0x80) >> 7;
         processor.v[x] <<= 1;
+
+        Ok(())
     }
 }
 impl OpCode for OpCode9XY0 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
 
         if processor.v[x] != processor.v[y] {
-            processor.pc += 2;
+            skip_next(processor);
         }
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeANNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         processor.i = data[0];
+        Ok(())
     }
 }
 impl OpCode for OpCodeBNNN {
-    // BXNN for newer systems
-    fn execute(processor: &mut Processor, data: &[u16]) {
-        if processor.compatibility == Compatibility::New {
-            panic!("BXNN is not supported on newer systems!");
-        }
-
+    // BNNN for original systems
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let nnn = data[0];
         processor.pc = nnn + processor.v[0] as u16;
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeBXNN {
-    // BXNN for original systems
-    fn execute(processor: &mut Processor, data: &[u16]) {
-        if processor.compatibility == Compatibility::Original {
-            panic!("BXNN is not supported on original systems!");
-        }
-
+    // BXNN for newer systems
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let nnn = data[1]; // X is included
         processor.pc = nnn + processor.v[x] as u16;
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeCXNN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let nn = data[1];
-        let random = rand::random::<u8>();
+        let random = processor.rng.next_u8();
 
         processor.v[x] = random & nn as u8;
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeDXYN {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    /** N = 0 draws a 16x16 sprite of two bytes per row */
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let y = data[1] as usize;
         let n = data[2];
 
-        let sprite_x = processor.v[x] as usize % Screen::WIDTH;
-        let sprite_y = processor.v[y] as usize % Screen::HEIGHT;
-        let height = n as usize;
-        let width = 8;
+        let screen_width = processor.width();
+        let screen_height = processor.height();
+        // The starting position always wraps, the quirk decides what happens to the pixels past the edges
+        let sprite_x = processor.v[x] as usize % screen_width;
+        let sprite_y = processor.v[y] as usize % screen_height;
+        let (width, height) = if n == 0 { (16, 16) } else { (8, n as usize) };
+        let row_bytes = width / 8;
+        let sprite_len = height * row_bytes;
         let mut flipped = false;
-
-        for row in 0..height {
-            let sprite = processor.memory.data[processor.i as usize + row];
-            debug!("Row {:#02}: {:#010b}", row, sprite);
-
-            for col in 0..width {
-                let sprite_bit = (sprite >> (width - 1 - col)) & 0x1;
-                let gfx_i = (sprite_y + row) * Screen::WIDTH + (sprite_x + col);
-
-                if gfx_i >= processor.gfx.len() {
-                    continue;
+        check_memory(
+            processor,
+            processor.i as usize,
+            sprite_len * processor.planes.count_ones() as usize,
+        )?;
+
+        // With both XO-CHIP bitplanes selected, plane 2's sprite follows plane 1's in memory
+        let planes = [1, 2]
+            .into_iter()
+            .filter(|plane| processor.planes & plane != 0);
+        for (index, plane) in planes.enumerate() {
+            let sprite_address = processor.i as usize + index * sprite_len;
+
+            for row in 0..height {
+                let mut pixel_y = sprite_y + row;
+                if pixel_y >= screen_height {
+                    if !processor.quirks.wrap {
+                        break;
+                    }
+                    pixel_y %= screen_height;
                 }
-
-                let prev_gfx = processor.gfx[gfx_i];
-                processor.gfx[gfx_i] ^= sprite_bit;
-
-                if prev_gfx == 1 && processor.gfx[gfx_i] == 0 {
-                    flipped = true;
+                let address = sprite_address + row * row_bytes;
+                let sprite = processor.memory.data[address..address + row_bytes]
+                    .iter()
+                    .fold(0_u16, |sprite, byte| sprite << 8 | *byte as u16);
+                debug!("Row {:#02}: {:#0w$b}", row, sprite, w = width + 2);
+
+                for col in 0..width {
+                    let mut pixel_x = sprite_x + col;
+                    if pixel_x >= screen_width {
+                        if !processor.quirks.wrap {
+                            break;
+                        }
+                        pixel_x %= screen_width;
+                    }
+                    if (sprite >> (width - 1 - col)) & 0x1 == 0 {
+                        continue;
+                    }
+                    let gfx_i = pixel_y * screen_width + pixel_x;
+
+                    if processor.gfx[gfx_i] & plane != 0 {
+                        flipped = true;
+                    }
+                    processor.gfx[gfx_i] ^= plane;
                 }
             }
         }
 
         processor.v[0xF] = flipped as u8;
         debug!("Flipped: {}", flipped);
+        // SCHIP only waits in low resolution, the VIP's hi-res interpreter waits too
+        if processor.quirks.display_wait && processor.resolution != Resolution::High {
+            processor.state = ProcessorState::WaitingForVblank;
+        }
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeEX9E {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
-        let keypad = keypad::INSTANCE.lock().unwrap();
 
-        if keypad.is_key_pressed() && processor.v[x] == keypad.get_current_key() {
-            processor.pc += 2;
+        if processor.keypad.is_pressed(processor.v[x]) {
+            skip_next(processor);
         }
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeEXA1 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
-        let keypad = keypad::INSTANCE.lock().unwrap();
 
-        if (keypad.is_key_pressed() && processor.v[x] != keypad.get_current_key())
-            || !keypad.is_key_pressed()
-        {
-            processor.pc += 2;
+        if !processor.keypad.is_pressed(processor.v[x]) {
+            skip_next(processor);
         }
+
+        Ok(())
+    }
+}
+impl OpCode for OpCodeF000 {
+    /** Loads the 16-bit address in the next two bytes into I and skips over them */
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        let pc = processor.pc as usize;
+        check_memory(processor, pc, 2)?;
+        processor.i =
+            u16::from_be_bytes([processor.memory.data[pc], processor.memory.data[pc + 1]]);
+        processor.pc = processor.pc.wrapping_add(2);
+
+        Ok(())
+    }
+}
+impl OpCode for OpCodeFN01 {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        processor.planes = data[0] as u8 & 0x3;
+        Ok(())
+    }
+}
+impl OpCode for OpCodeF002 {
+    fn execute(processor: &mut Processor, _: &[u16]) -> Result<(), EmulatorError> {
+        let i = processor.i as usize;
+        check_memory(processor, i, 16)?;
+        let mut pattern = [0; 16];
+        pattern.copy_from_slice(&processor.memory.data[i..i + 16]);
+        processor.audio_pattern = Some(pattern);
+
+        Ok(())
+    }
+}
+impl OpCode for OpCodeFX3A {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        let x = data[0] as usize;
+        processor.pitch = processor.v[x];
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX07 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         processor.v[x] = processor.delay_timer;
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX0A {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
-        let keypad = keypad::INSTANCE.lock().unwrap();
 
-        if !keypad.is_key_pressed() {
-            processor.pc -= 2;
-        } else {
-            processor.v[x] = keypad.get_current_key();
-        }
+        processor.state = ProcessorState::WaitingForKey { x, key: None };
+        processor.update_key_wait();
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX15 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         processor.delay_timer = processor.v[x];
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX18 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         processor.sound_timer = processor.v[x];
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX1E {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         processor.i = processor.i.wrapping_add(processor.v[x] as u16);
 
-        if processor.i > 0x0FFF {
-            processor.v[0xF] = 1;
+        if processor.quirks.fx1e_overflow {
+            processor.v[0xF] = (processor.i > 0x0FFF) as u8;
         }
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX29 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let digit = processor.v[x] as usize;
         processor.i = Memory::FONT_BEGIN_INDEX + (digit * 5) as u16;
+
+        Ok(())
+    }
+}
+impl OpCode for OpCodeFX30 {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        let x = data[0] as usize;
+        let digit = processor.v[x] as usize;
+        processor.i = Memory::BIG_FONT_BEGIN_INDEX + (digit * 10) as u16;
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX33 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
         let value = processor.v[x];
 
@@ -357,40 +634,72 @@ impl OpCode for OpCodeFX33 {
         let tens = (value / 10) % 10;
         let hundreds = value / 100;
 
+        check_memory(processor, processor.i as usize, 3)?;
         processor.memory.data[processor.i as usize] = hundreds;
         processor.memory.data[processor.i as usize + 1] = tens;
         processor.memory.data[processor.i as usize + 2] = ones;
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX55 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
 
+        check_memory(processor, processor.i as usize, x + 1)?;
         for i in 0..=x {
             processor.memory.data[processor.i as usize + i] = processor.v[i];
-            if processor.compatibility == Compatibility::Original {
-                processor.i += 1;
-            }
         }
+        if !processor.quirks.load_store {
+            processor.i = processor.i.wrapping_add(x as u16 + 1);
+        }
+
+        Ok(())
     }
 }
 impl OpCode for OpCodeFX65 {
-    fn execute(processor: &mut Processor, data: &[u16]) {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
         let x = data[0] as usize;
 
+        check_memory(processor, processor.i as usize, x + 1)?;
         for i in 0..=x {
             processor.v[i] = processor.memory.data[processor.i as usize + i];
-            if processor.compatibility == Compatibility::Original {
-                processor.i += 1;
-            }
         }
+        if !processor.quirks.load_store {
+            processor.i = processor.i.wrapping_add(x as u16 + 1);
+        }
+
+        Ok(())
     }
 }
+impl OpCode for OpCodeFX75 {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        let x = data[0] as usize;
+        processor.rpl[..=x].copy_from_slice(&processor.v[..=x]);
 
+        Ok(())
+    }
+}
+impl OpCode for OpCodeFX85 {
+    fn execute(processor: &mut Processor, data: &[u16]) -> Result<(), EmulatorError> {
+        let x = data[0] as usize;
+        processor.v[..=x].copy_from_slice(&processor.rpl[..=x]);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
 #[allow(non_snake_case)]
 mod tests {
     use super::*;
     use crate::components::memory::Memory;
+    use crate::components::processor::KeyTrigger;
+    use crate::components::rng::Rng;
+    use crate::components::screen::Screen;
+    use crate::components::stack::Stack;
+    use crate::error::EmulatorError;
+    use crate::quirks::{Platform, Quirks};
     use array_init::array_init;
     use wasm_bindgen_test::wasm_bindgen_test;
 
@@ -400,7 +709,7 @@ mod tests {
             .unwrap();
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_00E0() {
         // Arrange
         let mut processor = Processor::init();
@@ -413,7 +722,7 @@ mod tests {
         assert_eq!(processor.gfx, array_init(|_| 0));
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_00EE() {
         // Arrange
         let mut processor = Processor::init();
@@ -431,8 +740,138 @@ mod tests {
         );
         assert!(processor.stack.is_empty(), "Stack not popped!");
     }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_00EE_empty_stack() {
+        // Arrange
+        let mut processor = Processor::init();
+
+        // Act
+        let result = processor.execute(0x0, 0x0EE);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EmulatorError::StackUnderflow { opcode: 0x00EE, .. })
+        ));
+    }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_00CN() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.set_resolution(Resolution::High);
+        processor.gfx[5] = 1;
+        processor.gfx[62 * Screen::MAX_WIDTH] = 1;
+
+        // Act
+        execute_instruction(&mut processor, 0x00C3);
+
+        // Assert
+        assert_eq!(processor.gfx[3 * Screen::MAX_WIDTH + 5], 1);
+        assert_eq!(processor.gfx[5], 0);
+        assert!(
+            processor
+                .framebuffer()
+                .iter()
+                .filter(|&&pixel| pixel == 1)
+                .count()
+                == 1,
+            "Rows scrolled past the bottom should be gone"
+        );
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_00FB_00FC() {
+        // Arrange
+        let mut right = Processor::init();
+        let mut left = Processor::init();
+        for processor in [&mut right, &mut left] {
+            processor.gfx[Screen::WIDTH + 2] = 1;
+            processor.gfx[Screen::WIDTH + 62] = 1;
+        }
+
+        // Act
+        execute_instruction(&mut right, 0x00FB);
+        execute_instruction(&mut left, 0x00FC);
+
+        // Assert
+        assert_eq!(right.gfx[Screen::WIDTH + 6], 1);
+        assert_eq!(
+            right.gfx[2 * Screen::WIDTH + 2],
+            0,
+            "Nothing moves to the next row"
+        );
+        assert_eq!(
+            right
+                .framebuffer()
+                .iter()
+                .filter(|&&pixel| pixel == 1)
+                .count(),
+            1
+        );
+        assert_eq!(left.gfx[Screen::WIDTH + 58], 1);
+        assert_eq!(
+            left.framebuffer()
+                .iter()
+                .filter(|&&pixel| pixel == 1)
+                .count(),
+            1
+        );
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_00FD() {
+        // Arrange
+        let mut processor = Processor::init();
+
+        // Act
+        execute_instruction(&mut processor, 0x00FD);
+        processor.cycle().unwrap();
+
+        // Assert
+        assert_eq!(processor.state, ProcessorState::Exited);
+        assert_eq!(processor.pc, 0x200, "Nothing runs after exiting");
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_00FE_00FF() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.gfx[0] = 1;
+
+        // Act
+        execute_instruction(&mut processor, 0x00FF);
+        let high = (processor.width(), processor.height(), processor.gfx[0]);
+        processor.gfx[0] = 1;
+        execute_instruction(&mut processor, 0x00FE);
+
+        // Assert
+        assert_eq!(high, (128, 64, 0));
+        assert_eq!((processor.width(), processor.height()), (64, 32));
+        assert_eq!(
+            processor.gfx[0], 0,
+            "Switching resolution clears the screen"
+        );
+        assert_eq!(processor.framebuffer().len(), 64 * 32);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_00DN() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.planes = 0b10;
+        processor.gfx[5 * Screen::WIDTH] = 0b11;
+
+        // Act
+        execute_instruction(&mut processor, 0x00D2);
+
+        // Assert
+        assert_eq!(processor.gfx[3 * Screen::WIDTH], 0b10);
+        assert_eq!(
+            processor.gfx[5 * Screen::WIDTH],
+            0b01,
+            "Only the selected plane moves"
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_1NNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -445,7 +884,7 @@ mod tests {
         assert_eq!(processor.pc, jump_to);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_2NNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -456,14 +895,32 @@ mod tests {
 
         // Assert
         assert_eq!(
-            processor.stack[0],
+            processor.stack.as_slice()[0],
             Memory::ROM_BEGIN_INDEX,
             "PC not added to stack!"
         );
         assert_eq!(processor.pc, nnn, "PC should be {:06X}!", nnn);
     }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_2NNN_full_stack() {
+        // Arrange
+        let mut processor = Processor::init();
+        for _ in 0..processor.stack.depth() {
+            processor.stack.push(0x200);
+        }
+
+        // Act
+        let result = processor.execute(0x2, 0x123);
 
-    #[wasm_bindgen_test]
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EmulatorError::StackOverflow { opcode: 0x2123, .. })
+        ));
+        assert_eq!(processor.stack.len(), Stack::VIP_DEPTH);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_3XNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -478,7 +935,7 @@ mod tests {
         assert_eq!(processor.pc, Memory::ROM_BEGIN_INDEX + 0x2);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_4XNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -493,7 +950,7 @@ mod tests {
         assert_eq!(processor.pc, Memory::ROM_BEGIN_INDEX + 0x2);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_5XY0() {
         // Arrange
         let mut processor = Processor::init();
@@ -509,7 +966,27 @@ mod tests {
         assert_eq!(processor.pc, Memory::ROM_BEGIN_INDEX + 0x2);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_5XY2_5XY3() {
+        // Arrange
+        let mut processor = Processor::init_quirks(Quirks::xo_chip());
+        processor.i = 0x300;
+        processor.v[..4].copy_from_slice(&[1, 2, 3, 4]);
+
+        // Act
+        execute_instruction(&mut processor, 0x5132);
+        execute_instruction(&mut processor, 0x5312);
+        let stored = processor.memory.data[0x300..0x303].to_vec();
+        processor.v = [0; 16];
+        execute_instruction(&mut processor, 0x5133);
+
+        // Assert
+        assert_eq!(stored, [4, 3, 2], "X > Y stores in reverse");
+        assert_eq!(processor.v[1..4], [4, 3, 2]);
+        assert_eq!(processor.i, 0x300);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_6XNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -523,7 +1000,7 @@ mod tests {
         assert_eq!(processor.v[x as usize] as u16, nn);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_7XNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -538,7 +1015,7 @@ mod tests {
         assert_eq!(processor.v[x as usize] as u16, x + nn);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY0() {
         // Arrange
         let mut processor = Processor::init();
@@ -553,7 +1030,7 @@ mod tests {
         assert_eq!(processor.v[x as usize], 0x23);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY1() {
         // Arrange
         let mut processor = Processor::init();
@@ -568,8 +1045,32 @@ mod tests {
         // Assert
         assert_eq!(processor.v[x as usize], 0x23 | 0x24);
     }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_8XY1_vf_reset() {
+        // Arrange
+        let mut vip = Processor::init_quirks(Quirks::cosmac_vip());
+        let mut schip = Processor::init_quirks(Quirks::schip_1_1());
+        for processor in [&mut vip, &mut schip] {
+            processor.v[0x1] = 0x23;
+            processor.v[0x2] = 0x24;
+            processor.v[0xF] = 0x42;
+        }
+
+        // Act
+        execute_instruction(&mut vip, 0x8121);
+        execute_instruction(&mut schip, 0x8121);
+        execute_instruction(&mut vip, 0x8F12);
 
-    #[wasm_bindgen_test]
+        // Assert
+        assert_eq!(vip.v[0x1], 0x23 | 0x24);
+        assert_eq!(
+            vip.v[0xF], 0,
+            "VF should be reset even when it's the target"
+        );
+        assert_eq!(schip.v[0xF], 0x42);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY2() {
         // Arrange
         let mut processor = Processor::init();
@@ -585,7 +1086,7 @@ mod tests {
         assert_eq!(processor.v[x as usize], 0x23 & 0x24);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY3() {
         // Arrange
         let mut processor = Processor::init();
@@ -601,7 +1102,7 @@ mod tests {
         assert_eq!(processor.v[x as usize], 0x23 ^ 0x24);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY4_no_overflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -617,7 +1118,7 @@ mod tests {
         assert_eq!(processor.v[x as usize], 0x23 + 0x24, "v[x] should be 0x47");
         assert_eq!(processor.v[0xF], 0x0, "v[0xF] should be 0x0");
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY4_overflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -634,7 +1135,7 @@ mod tests {
         assert_eq!(processor.v[0xF], 0x1, "v[0xF] should be 0x1");
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY5_no_underflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -650,7 +1151,7 @@ mod tests {
         assert_eq!(processor.v[x as usize], 0x24 - 0x23, "v[x] should be 0x1");
         assert_eq!(processor.v[0xF], 0x1, "v[0xF] should be 0x1");
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY5_underflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -667,10 +1168,10 @@ mod tests {
         assert_eq!(processor.v[0xF], 0x0, "v[0xF] should be 0x0");
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY6_original() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::Original);
+        let mut processor = Processor::init_quirks(Quirks::cosmac_vip());
         let x = 0x1;
         let y = 0x2;
         processor.v[y as usize] = 0x23;
@@ -692,10 +1193,10 @@ mod tests {
             0x23 & 0x1
         );
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY6_new() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::New);
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
         let x = 0x1;
         let y = 0x2;
         processor.v[x as usize] = 0x23;
@@ -718,7 +1219,7 @@ mod tests {
         );
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY7_no_underflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -734,7 +1235,7 @@ mod tests {
         assert_eq!(processor.v[x as usize], 0x24 - 0x23, "v[x] should be 0x1");
         assert_eq!(processor.v[0xF], 0x1, "v[0xF] should be 0x1");
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XY7_underflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -751,10 +1252,10 @@ mod tests {
         assert_eq!(processor.v[0xF], 0x0, "v[0xF] should be 0x0");
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XYE_original() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::Original);
+        let mut processor = Processor::init_quirks(Quirks::cosmac_vip());
         let x = 0x1;
         let y = 0x2;
         processor.v[y as usize] = 0x23;
@@ -776,10 +1277,10 @@ mod tests {
             (0x23 & 0x80) >> 7
         );
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_8XYE_new() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::New);
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
         let x = 0x1;
         let y = 0x2;
         processor.v[x as usize] = 0x23;
@@ -802,7 +1303,7 @@ mod tests {
         );
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_9XY0() {
         // Arrange
         let mut processor = Processor::init();
@@ -818,7 +1319,7 @@ mod tests {
         assert_eq!(processor.pc, Memory::ROM_BEGIN_INDEX + 0x2);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_ANNN() {
         // Arrange
         let mut processor = Processor::init();
@@ -831,10 +1332,10 @@ mod tests {
         assert_eq!(processor.i, nnn);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_BNNN() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::Original);
+        let mut processor = Processor::init_quirks(Quirks::cosmac_vip());
         let nnn = 0x123;
         processor.v[0] = 0x1;
 
@@ -844,10 +1345,10 @@ mod tests {
         // Assert
         assert_eq!(processor.pc, nnn + 0x1);
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_BXNN() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::New);
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
         let x = 0x1_u16;
         let nnn = 0x123;
         processor.v[x as usize] = 0x2;
@@ -859,22 +1360,23 @@ mod tests {
         assert_eq!(processor.pc, nnn + 0x2);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_CXNN() {
         // Arrange
         let mut processor = Processor::init();
+        processor.rng = Rng::from_seed(42);
         let x = 0x1;
         let nn = 0x23;
-        let old_vx = processor.v[x as usize];
+        let expected = Rng::from_seed(42).next_u8() & nn as u8;
 
         // Act
         execute_instruction(&mut processor, 0xC000 | (x << 8) | nn);
 
         // Assert
-        assert_ne!(old_vx, processor.v[x as usize]);
+        assert_eq!(processor.v[x as usize], expected);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_DXYN_no_flip() {
         // Arrange
         let mut processor = Processor::init();
@@ -901,7 +1403,7 @@ mod tests {
         );
         assert_eq!(processor.v[0xF], 0x0, "v[0xF] should be 0x0");
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_DXYN_flip() {
         // Arrange
         let mut processor = Processor::init();
@@ -930,13 +1432,172 @@ mod tests {
         assert_eq!(processor.v[0xF], 0x1, "v[0xF] should be 0x1");
     }
 
-    #[wasm_bindgen_test]
+    /** Draws an 8x4 block with VX = `sprite_x` and VY = `sprite_y`, returns the lit pixels as (x, y) */
+    fn draw_block(wrap: bool, sprite_x: u8, sprite_y: u8) -> Vec<(usize, usize)> {
+        let mut processor = Processor::init();
+        processor.quirks.wrap = wrap;
+        processor.v[0x1] = sprite_x;
+        processor.v[0x2] = sprite_y;
+        processor.i = 0x300;
+        processor.memory.data[0x300..0x304].copy_from_slice(&[0xFF; 4]);
+        execute_instruction(&mut processor, 0xD124);
+
+        let framebuffer = processor.framebuffer();
+        (0..framebuffer.len())
+            .filter(|&i| framebuffer[i] == 1)
+            .map(|i| (i % Screen::WIDTH, i / Screen::WIDTH))
+            .collect()
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_DXYN_right_edge() {
+        // Act
+        let clipped = draw_block(false, 60, 0);
+        let wrapped = draw_block(true, 60, 0);
+
+        // Assert
+        assert_eq!(clipped.len(), 16);
+        assert!(
+            clipped.iter().all(|&(x, _)| x >= 60),
+            "Nothing should bleed into the next row"
+        );
+        assert_eq!(wrapped.len(), 32);
+        assert!(wrapped.contains(&(0, 0)) && wrapped.contains(&(3, 3)));
+        assert!(!wrapped.contains(&(4, 0)));
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_DXYN_bottom_edge() {
+        // Act
+        let clipped = draw_block(false, 0, 30);
+        let wrapped = draw_block(true, 0, 30);
+
+        // Assert
+        assert_eq!(clipped.len(), 16);
+        assert!(clipped.iter().all(|&(_, y)| y >= 30));
+        assert_eq!(wrapped.len(), 32);
+        assert!(wrapped.contains(&(7, 0)) && wrapped.contains(&(7, 1)));
+        assert!(!wrapped.contains(&(0, 2)));
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_DXYN_left_edge() {
+        // Act
+        // 0xFF starts at x = 63, so the rest of the sprite is past the right edge and comes back on the left
+        let clipped = draw_block(false, 0xFF, 0);
+        let wrapped = draw_block(true, 0xFF, 0);
+        let at_zero = draw_block(false, 64, 0);
+
+        // Assert
+        assert_eq!(clipped, [(63, 0), (63, 1), (63, 2), (63, 3)]);
+        assert_eq!(wrapped.len(), 32);
+        assert!(wrapped.contains(&(0, 0)) && wrapped.contains(&(6, 3)));
+        assert!(at_zero.contains(&(0, 0)) && at_zero.contains(&(7, 3)));
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_DXYN_top_edge() {
+        // Act
+        // 0xFF starts at y = 31, so the rest of the sprite is past the bottom edge and comes back at the top
+        let clipped = draw_block(false, 0, 0xFF);
+        let wrapped = draw_block(true, 60, 0xFF);
+
+        // Assert
+        assert_eq!(clipped.len(), 8);
+        assert!(clipped.iter().all(|&(_, y)| y == 31));
+        assert_eq!(wrapped.len(), 32);
+        assert!(
+            wrapped.contains(&(63, 31)) && wrapped.contains(&(0, 0)) && wrapped.contains(&(3, 2))
+        );
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_DXY0() {
+        // Arrange
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
+        processor.set_resolution(Resolution::High);
+        processor.v[0x1] = 120;
+        processor.v[0x2] = 60;
+        processor.i = 0x300;
+        for row in 0..16 {
+            processor.memory.data[0x300 + row * 2] = 0x80;
+            processor.memory.data[0x301 + row * 2] = 0x01;
+        }
+
+        // Act
+        execute_instruction(&mut processor, 0xD120);
+
+        // Assert
+        let lit: Vec<usize> = (0..processor.framebuffer().len())
+            .filter(|&i| processor.gfx[i] == 1)
+            .collect();
+        assert_eq!(
+            lit,
+            (60..64)
+                .map(|row| row * Screen::MAX_WIDTH + 120)
+                .collect::<Vec<_>>(),
+            "Only the left column fits, the rest is clipped"
+        );
+        assert_eq!(processor.state, ProcessorState::Running);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_DXYN_planes() {
+        // Arrange
+        let mut processor = Processor::init_quirks(Quirks::xo_chip());
+        processor.i = 0x300;
+        processor.memory.data[0x300..0x302].copy_from_slice(&[0x80, 0xC0]);
+        processor.gfx[0] = 0b01;
+
+        // Act
+        execute_instruction(&mut processor, 0xF301);
+        execute_instruction(&mut processor, 0xD001);
+        let drawn = processor.gfx[..2].to_vec();
+        processor.gfx[2] = 0b11;
+        execute_instruction(&mut processor, 0xF101);
+        execute_instruction(&mut processor, 0x00E0);
+
+        // Assert
+        assert_eq!(processor.v[0xF], 1, "Plane 1 turned a pixel off");
+        assert_eq!(drawn, [0b10, 0b10], "Plane 2's sprite follows plane 1's");
+        assert_eq!(
+            processor.gfx[..3],
+            [0b10, 0b10, 0b10],
+            "00E0 only clears plane 1"
+        );
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_EX9E() {
         // Arrange
         let mut processor = Processor::init();
         let x = 0x1;
         processor.v[x as usize] = 0x1;
-        keypad::INSTANCE.lock().unwrap().set_key(0x1);
+        processor.keypad.press(0x1);
+
+        // Act
+        execute_instruction(&mut processor, 0xE09E | (x << 8));
+
+        // Assert
+        assert_eq!(processor.pc, 0x202);
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_EX9E_other_key_also_pressed() {
+        // Arrange
+        let mut processor = Processor::init();
+        let x = 0x1;
+        processor.v[x as usize] = 0xC;
+        processor.keypad.press(0x1);
+        processor.keypad.press(0xC);
+
+        // Act
+        execute_instruction(&mut processor, 0xE09E | (x << 8));
+
+        // Assert
+        assert_eq!(processor.pc, 0x202);
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_EX9E_key_0() {
+        // Arrange
+        let mut processor = Processor::init();
+        let x = 0x1;
+        processor.v[x as usize] = 0x0;
+        processor.keypad.press(0x0);
 
         // Act
         execute_instruction(&mut processor, 0xE09E | (x << 8));
@@ -945,13 +1606,13 @@ mod tests {
         assert_eq!(processor.pc, 0x202);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_EXA1_wrong_key() {
         // Arrange
         let mut processor = Processor::init();
         let x = 0x1;
         processor.v[x as usize] = 0x1;
-        keypad::INSTANCE.lock().unwrap().set_key(0x2);
+        processor.keypad.press(0x2);
 
         // Act
         execute_instruction(&mut processor, 0xE0A1 | (x << 8));
@@ -959,13 +1620,13 @@ mod tests {
         // Assert
         assert_eq!(processor.pc, 0x202);
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_EXA1_no_key() {
         // Arrange
         let mut processor = Processor::init();
         let x = 0x1;
         processor.v[x as usize] = 0x1;
-        keypad::INSTANCE.lock().unwrap().unset_key();
+        processor.keypad.release_all();
 
         // Act
         execute_instruction(&mut processor, 0xE0A1 | (x << 8));
@@ -974,7 +1635,41 @@ mod tests {
         assert_eq!(processor.pc, 0x202);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_F000() {
+        // Arrange
+        let mut processor = Processor::init_quirks(Quirks::xo_chip());
+        // F000 ABCD, then 3000 (V0 == 0) skips over the whole of the next F000 ABCD
+        let rom = [0xF0, 0x00, 0xAB, 0xCD, 0x30, 0x00, 0xF0, 0x00, 0x12, 0x34];
+        processor.memory.data[0x200..0x20A].copy_from_slice(&rom);
+
+        // Act
+        processor.cycle().unwrap();
+        let i = processor.i;
+        processor.cycle().unwrap();
+
+        // Assert
+        assert_eq!(i, 0xABCD);
+        assert_eq!(processor.pc, 0x20A);
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_F002_FX3A() {
+        // Arrange
+        let mut processor = Processor::init_quirks(Quirks::xo_chip());
+        processor.i = 0x300;
+        processor.memory.data[0x300..0x310].copy_from_slice(&[0xF0; 16]);
+        processor.v[0x1] = 112;
+
+        // Act
+        execute_instruction(&mut processor, 0xF002);
+        execute_instruction(&mut processor, 0xF13A);
+
+        // Assert
+        assert_eq!(processor.audio_pattern, Some([0xF0; 16]));
+        assert_eq!(processor.audio_sample_rate(), 8000.0);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX07() {
         // Arrange
         let mut processor = Processor::init();
@@ -988,24 +1683,62 @@ mod tests {
         assert_eq!(processor.v[x as usize], processor.delay_timer);
     }
 
-    #[wasm_bindgen_test]
-    fn test_FX0A() {
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX0A_press() {
         // Arrange
         let mut processor = Processor::init();
         let x = 0x1;
-        keypad::INSTANCE.lock().unwrap().set_key(0x1);
+        processor.key_trigger = KeyTrigger::Press;
+        processor.keypad.press(0x1);
 
         // Act
         execute_instruction(&mut processor, 0xF00A | (x << 8));
 
         // Assert
-        assert_eq!(
-            processor.v[x as usize],
-            keypad::INSTANCE.lock().unwrap().get_current_key()
+        assert_eq!(processor.v[x as usize], 0x1);
+        assert_eq!(processor.state, ProcessorState::Running);
+    }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX0A_release() {
+        // Arrange
+        let mut processor = Processor::init();
+        let x = 0x1;
+        processor.key_trigger = KeyTrigger::Release;
+        processor.keypad.press(0x1);
+
+        // Act
+        execute_instruction(&mut processor, 0xF00A | (x << 8));
+        let waiting_while_held = processor.is_waiting_for_key();
+        processor.keypad.release(0x1);
+        processor.cycle().unwrap();
+
+        // Assert
+        assert!(
+            waiting_while_held,
+            "FX0A should wait for the key to be released"
         );
+        assert_eq!(processor.v[x as usize], 0x1);
+        assert_eq!(processor.state, ProcessorState::Running);
     }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX0A_waiting() {
+        // Arrange
+        let mut processor = Processor::init();
+        let x = 0x1;
+        let pc = processor.pc;
+        processor.delay_timer = 0x23;
+
+        // Act
+        execute_instruction(&mut processor, 0xF00A | (x << 8));
+        processor.run_frame(10).unwrap();
 
-    #[wasm_bindgen_test]
+        // Assert
+        assert!(processor.is_waiting_for_key());
+        assert_eq!(processor.pc, pc, "PC should not move while waiting");
+        assert_eq!(processor.delay_timer, 0x22, "Timers should keep counting");
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX15() {
         // Arrange
         let mut processor = Processor::init();
@@ -1019,7 +1752,7 @@ mod tests {
         assert_eq!(processor.delay_timer, processor.v[x as usize]);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX18() {
         // Arrange
         let mut processor = Processor::init();
@@ -1033,7 +1766,7 @@ mod tests {
         assert_eq!(processor.sound_timer, processor.v[x as usize]);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX1E_no_overflow() {
         // Arrange
         let mut processor = Processor::init();
@@ -1048,10 +1781,28 @@ mod tests {
         assert_eq!(processor.i, 0x200 + 0x23);
         assert_eq!(processor.v[0xF], 0x0);
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX1E_overflow_quirk_no_overflow() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.quirks.fx1e_overflow = true;
+        let x = 0x1;
+        processor.v[x as usize] = 0x1;
+        processor.v[0xF] = 0x1;
+        processor.i = 0x0FFE;
+
+        // Act
+        execute_instruction(&mut processor, 0xF01E | (x << 8));
+
+        // Assert
+        assert_eq!(processor.i, 0x0FFF);
+        assert_eq!(processor.v[0xF], 0x0, "a stale VF should be cleared");
+    }
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX1E_overflow() {
         // Arrange
         let mut processor = Processor::init();
+        processor.quirks.fx1e_overflow = true;
         let x = 0x1;
         processor.v[x as usize] = 0x1;
         processor.i = 0x0FFF;
@@ -1064,7 +1815,7 @@ mod tests {
         assert_eq!(processor.v[0xF], 0x1, "v[0xF] should be 0x1");
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX29() {
         // Arrange
         let mut processor = Processor::init();
@@ -1078,7 +1829,21 @@ mod tests {
         assert_eq!(processor.i, Memory::FONT_BEGIN_INDEX + 4 * 5);
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX30() {
+        // Arrange
+        let mut processor = Processor::init();
+        let x = 0x1;
+        processor.v[x as usize] = 0x4;
+
+        // Act
+        execute_instruction(&mut processor, 0xF030 | (x << 8));
+
+        // Assert
+        assert_eq!(processor.i, Memory::BIG_FONT_BEGIN_INDEX + 4 * 10);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX33() {
         // Arrange
         let mut processor = Processor::init();
@@ -1093,11 +1858,31 @@ mod tests {
         assert_eq!(processor.memory.data[processor.i as usize + 1], 5);
         assert_eq!(processor.memory.data[processor.i as usize + 2], 4);
     }
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX33_out_of_bounds() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.i = 0xFFE;
 
-    #[wasm_bindgen_test]
+        // Act
+        let result = processor.execute(0xF, 0x133);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(EmulatorError::MemoryOutOfBounds {
+                opcode: 0xF133,
+                address: 0x1000,
+                ..
+            })
+        ));
+        assert_eq!(processor.memory.data[0xFFE], 0, "Nothing should be written");
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX55_original() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::Original);
+        let mut processor = Processor::init_quirks(Quirks::cosmac_vip());
         let x = 0x1_u16;
         for i in 0..=x as usize {
             processor.v[i] = i as u8;
@@ -1110,15 +1895,16 @@ mod tests {
         // Assert
         for i in 0..=x as usize {
             assert_eq!(
-                processor.memory.data[(processor.i - x) as usize + i],
+                processor.memory.data[Memory::ROM_BEGIN_INDEX as usize + i],
                 processor.v[i]
             );
         }
+        assert_eq!(processor.i, Memory::ROM_BEGIN_INDEX + x + 1);
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX55_new() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::New);
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
         let x = 0x1_u16;
         for i in 0..=x as usize {
             processor.v[i] = i as u8;
@@ -1137,10 +1923,10 @@ mod tests {
         }
     }
 
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX65_original() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::Original);
+        let mut processor = Processor::init_quirks(Quirks::cosmac_vip());
         let x = 0x1_u16;
         processor.i = Memory::ROM_BEGIN_INDEX;
         for i in 0..=x as usize {
@@ -1152,16 +1938,14 @@ mod tests {
 
         // Assert
         for i in 0..=x as usize {
-            assert_eq!(
-                processor.v[i],
-                processor.memory.data[(processor.i) as usize + i]
-            );
+            assert_eq!(processor.v[i], i as u8);
         }
+        assert_eq!(processor.i, Memory::ROM_BEGIN_INDEX + x + 1);
     }
-    #[wasm_bindgen_test]
+    #[wasm_bindgen_test(unsupported = test)]
     fn test_FX65_new() {
         // Arrange
-        let mut processor = Processor::init_compat(Compatibility::New);
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
         let x = 0x1_u16;
         processor.i = Memory::ROM_BEGIN_INDEX;
         for i in 0..=x as usize {
@@ -1179,4 +1963,42 @@ mod tests {
             );
         }
     }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX55_FX65_wrap_i() {
+        // Arrange
+        let mut processor = Processor::init_platform(Quirks::xo_chip(), Platform::xo_chip());
+        processor.v[0] = 0x2A;
+
+        // Act
+        processor.i = 0xFFFF;
+        execute_instruction(&mut processor, 0xF055);
+        let after_store = processor.i;
+        processor.v[0] = 0;
+        processor.i = 0xFFFF;
+        execute_instruction(&mut processor, 0xF065);
+
+        // Assert
+        assert_eq!(after_store, 0);
+        assert_eq!(processor.i, 0);
+        assert_eq!(processor.v[0], 0x2A);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_FX75_FX85() {
+        // Arrange
+        let mut processor = Processor::init_quirks(Quirks::schip_1_1());
+        for i in 0..=7 {
+            processor.v[i] = i as u8 + 1;
+        }
+
+        // Act
+        execute_instruction(&mut processor, 0xF775);
+        processor.v = [0; 16];
+        execute_instruction(&mut processor, 0xF385);
+
+        // Assert
+        assert_eq!(processor.rpl[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(processor.v[..5], [1, 2, 3, 4, 0]);
+    }
 }
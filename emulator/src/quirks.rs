@@ -0,0 +1,290 @@
+use crate::components::memory::Memory;
+use crate::components::processor::KeyTrigger;
+use crate::components::stack::Stack;
+use wasm_bindgen::prelude::*;
+
+/** Preset names with case and anything but letters and digits left out, so `SCHIP-1.1` matches `schip11` */
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/** The interpreters with presets, so a name picks the same one for `Quirks` and `Platform` */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    CosmacVip,
+    Chip48,
+    Schip10,
+    Schip11,
+    XoChip,
+}
+impl Preset {
+    /** Names are those in `Quirks::PRESETS`, case and dashes are ignored so `SCHIP-1.1` works too */
+    pub fn from_name(name: &str) -> Result<Preset, Box<dyn std::error::Error>> {
+        let preset = match normalize(name).as_str() {
+            "vip" | "cosmacvip" | "chip8" => Preset::CosmacVip,
+            "chip48" => Preset::Chip48,
+            "schip10" => Preset::Schip10,
+            "schip11" | "schip" => Preset::Schip11,
+            "xochip" => Preset::XoChip,
+            _ => return Err(format!("Unknown preset '{}'", name).into()),
+        };
+
+        Ok(preset)
+    }
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Preset::CosmacVip => Quirks::cosmac_vip(),
+            Preset::Chip48 => Quirks::chip_48(),
+            Preset::Schip10 => Quirks::schip_1_0(),
+            Preset::Schip11 => Quirks::schip_1_1(),
+            Preset::XoChip => Quirks::xo_chip(),
+        }
+    }
+    pub fn platform(self) -> Platform {
+        match self {
+            Preset::CosmacVip => Platform::cosmac_vip(),
+            Preset::Chip48 | Preset::Schip10 | Preset::Schip11 => Platform::schip(),
+            Preset::XoChip => Platform::xo_chip(),
+        }
+    }
+}
+
+/** Behaviors that differ between CHIP-8 interpreters. Each flag turns one quirk on */
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quirks {
+    /** 8XY6/8XYE shift VX in place instead of shifting VY into VX */
+    pub shift: bool,
+    /** FX55/FX65 leave I unchanged instead of moving it past the last register */
+    pub load_store: bool,
+    /** BNNN becomes BXNN and jumps to XNN + VX instead of NNN + V0 */
+    pub jump: bool,
+    /** 8XY1/8XY2/8XY3 reset VF to 0 */
+    pub vf_reset: bool,
+    /** Sprites wrap around to the other side of the screen instead of being clipped at the edges */
+    pub wrap: bool,
+    /** DXYN waits for the next 60 Hz frame before drawing */
+    pub display_wait: bool,
+    /** FX1E sets VF when I goes past 0xFFF */
+    pub fx1e_overflow: bool,
+}
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::schip_1_1()
+    }
+}
+impl Quirks {
+    pub const PRESETS: [&'static str; 5] = ["vip", "chip48", "schip10", "schip11", "xochip"];
+
+    /** See `Preset::from_name` for the names */
+    pub fn preset(name: &str) -> Result<Quirks, Box<dyn std::error::Error>> {
+        Ok(Preset::from_name(name)?.quirks())
+    }
+
+    /** One bit per flag in declaration order, for save states and movies */
+    pub fn to_bits(&self) -> u8 {
+        [
+            self.shift,
+            self.load_store,
+            self.jump,
+            self.vf_reset,
+            self.wrap,
+            self.display_wait,
+            self.fx1e_overflow,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (bit, &on)| bits | (on as u8) << bit)
+    }
+    pub fn from_bits(bits: u8) -> Quirks {
+        let on = |bit: u8| bits & 1 << bit != 0;
+
+        Quirks {
+            shift: on(0),
+            load_store: on(1),
+            jump: on(2),
+            vf_reset: on(3),
+            wrap: on(4),
+            display_wait: on(5),
+            fx1e_overflow: on(6),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Quirks {
+    /** The original interpreter */
+    pub fn cosmac_vip() -> Quirks {
+        Quirks {
+            shift: false,
+            load_store: false,
+            jump: false,
+            vf_reset: true,
+            wrap: false,
+            display_wait: true,
+            fx1e_overflow: false,
+        }
+    }
+    /** HP48 port, the start of shifting VX in place and leaving I alone */
+    pub fn chip_48() -> Quirks {
+        Quirks {
+            shift: true,
+            load_store: true,
+            jump: true,
+            vf_reset: false,
+            wrap: false,
+            display_wait: false,
+            fx1e_overflow: false,
+        }
+    }
+    /** Like CHIP-48, but low resolution drawing still waits for the frame */
+    pub fn schip_1_0() -> Quirks {
+        Quirks {
+            display_wait: true,
+            ..Quirks::chip_48()
+        }
+    }
+    pub fn schip_1_1() -> Quirks {
+        Quirks::chip_48()
+    }
+    /** Back to VIP shifts, loads and jumps, but with wrapping sprites and no frame waits */
+    pub fn xo_chip() -> Quirks {
+        Quirks {
+            shift: false,
+            load_store: false,
+            jump: false,
+            vf_reset: false,
+            wrap: true,
+            display_wait: false,
+            fx1e_overflow: false,
+        }
+    }
+    /** One of `vip`, `chip48`, `schip10`, `schip11` or `xochip` */
+    #[wasm_bindgen(js_name = preset)]
+    pub fn preset_js(name: &str) -> Result<Quirks, JsError> {
+        Quirks::preset(name).map_err(|err| JsError::new(&err.to_string()))
+    }
+}
+
+/** The machine behind a preset: its memory, stack and how FX0A takes keys */
+/** Kept apart from `Quirks`, so turning a single quirk on or off never changes the machine */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Platform {
+    /** Bytes of memory, 4 KB up to 64 KB */
+    pub memory_size: usize,
+    pub stack_depth: usize,
+    pub key_trigger: KeyTrigger,
+}
+impl Default for Platform {
+    fn default() -> Platform {
+        Platform::schip()
+    }
+}
+impl Platform {
+    /** See `Preset::from_name` for the names */
+    pub fn preset(name: &str) -> Result<Platform, Box<dyn std::error::Error>> {
+        Ok(Preset::from_name(name)?.platform())
+    }
+
+    /** 4 KB, 12 stack levels and FX0A waiting for the key to be released */
+    pub fn cosmac_vip() -> Platform {
+        Platform {
+            memory_size: Memory::SIZE,
+            stack_depth: Stack::VIP_DEPTH,
+            key_trigger: KeyTrigger::Release,
+        }
+    }
+    /** 4 KB, 16 stack levels and FX0A taking a key as soon as it's pressed, from CHIP-48 on */
+    pub fn schip() -> Platform {
+        Platform {
+            memory_size: Memory::SIZE,
+            stack_depth: Stack::MAX_DEPTH,
+            key_trigger: KeyTrigger::Press,
+        }
+    }
+    /** Like SUPER-CHIP, but with 64 KB */
+    pub fn xo_chip() -> Platform {
+        Platform {
+            memory_size: Memory::MAX_SIZE,
+            ..Platform::schip()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Platform, Preset, Quirks};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_preset() {
+        // Act
+        let presets: Vec<Quirks> = Quirks::PRESETS
+            .iter()
+            .map(|name| Quirks::preset(name).unwrap())
+            .collect();
+
+        // Assert
+        assert_eq!(presets[0], Quirks::cosmac_vip());
+        assert_eq!(presets[4], Quirks::xo_chip());
+        assert_eq!(Quirks::preset("SCHIP-1.1").unwrap(), Quirks::schip_1_1());
+        assert!(Quirks::preset("chip-10").is_err());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_platform_preset() {
+        // Act
+        let platforms: Vec<Platform> = Quirks::PRESETS
+            .iter()
+            .map(|name| Platform::preset(name).unwrap())
+            .collect();
+
+        // Assert
+        assert_eq!(platforms[0], Platform::cosmac_vip());
+        assert_eq!(platforms[1..4], [Platform::schip(); 3]);
+        assert_eq!(platforms[4], Platform::xo_chip());
+        assert!(Platform::preset("chip-10").is_err());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_preset_names() {
+        // Act
+        let presets: Vec<Preset> = ["chip-8", "CHIP-48", "schip_1.0", "SCHIP", "XO-CHIP"]
+            .iter()
+            .map(|name| Preset::from_name(name).unwrap())
+            .collect();
+
+        // Assert
+        assert_eq!(
+            presets,
+            [
+                Preset::CosmacVip,
+                Preset::Chip48,
+                Preset::Schip10,
+                Preset::Schip11,
+                Preset::XoChip
+            ]
+        );
+        assert_eq!(Preset::Chip48.quirks(), Quirks::chip_48());
+        assert_eq!(Preset::XoChip.platform(), Platform::xo_chip());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_bits() {
+        // Arrange
+        let quirks = Quirks {
+            fx1e_overflow: true,
+            ..Quirks::cosmac_vip()
+        };
+
+        // Act
+        let bits = quirks.to_bits();
+
+        // Assert
+        assert_eq!(bits, 0b0110_1000);
+        assert_eq!(Quirks::from_bits(bits), quirks);
+    }
+}
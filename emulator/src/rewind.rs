@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+
+/** Bytes that differ from a base state, enough to turn the base back into the state it was made from */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    len: usize,
+    /** Runs of changed bytes and where they start */
+    runs: Vec<(usize, Vec<u8>)>,
+}
+impl Delta {
+    /** Runs separated by fewer unchanged bytes than this are merged, which is cheaper than another run */
+    const MIN_GAP: usize = 8;
+
+    pub fn encode(base: &[u8], target: &[u8]) -> Delta {
+        let differs = |index: usize| base.get(index) != Some(&target[index]);
+
+        let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+        let mut index = 0;
+        while index < target.len() {
+            if !differs(index) {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            let mut end = index + 1;
+            let mut gap = 0;
+            while end + gap < target.len() && gap < Delta::MIN_GAP {
+                if differs(end + gap) {
+                    end += gap + 1;
+                    gap = 0;
+                } else {
+                    gap += 1;
+                }
+            }
+            runs.push((start, target[start..end].to_vec()));
+            index = end;
+        }
+
+        Delta {
+            len: target.len(),
+            runs,
+        }
+    }
+    pub fn apply(&self, base: &[u8]) -> Vec<u8> {
+        let mut target = base.to_vec();
+        target.resize(self.len, 0);
+        for (start, bytes) in &self.runs {
+            target[*start..*start + bytes.len()].copy_from_slice(bytes);
+        }
+
+        target
+    }
+
+    /** Bytes held by the delta, roughly */
+    pub fn size(&self) -> usize {
+        self.runs
+            .iter()
+            .map(|(_, bytes)| bytes.len() + std::mem::size_of::<(usize, Vec<u8>)>())
+            .sum()
+    }
+}
+
+/** History of save states for rewinding, taken every `interval` frames */
+/** Only the newest state is kept whole, older ones are deltas against the next newer one */
+#[derive(Debug, Clone)]
+pub struct Rewind {
+    depth: usize,
+    interval: u64,
+    /** Frame number, cycle count and save state of the newest snapshot */
+    latest: Option<(u64, u64, Vec<u8>)>,
+    /** Older snapshots, oldest first */
+    history: VecDeque<(u64, u64, Delta)>,
+}
+impl Rewind {
+    /** Keeps up to `depth` snapshots, one every `interval` frames */
+    pub fn new(depth: usize, interval: u32) -> Rewind {
+        Rewind {
+            depth: depth.max(1),
+            interval: interval.max(1) as u64,
+            latest: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    pub fn interval(&self) -> u32 {
+        self.interval as u32
+    }
+    /** Number of snapshots held */
+    pub fn len(&self) -> usize {
+        self.history.len() + self.latest.is_some() as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+    /** Frame of the oldest snapshot, as far back as `rewind` can go */
+    pub fn oldest_frame(&self) -> Option<u64> {
+        self.history
+            .front()
+            .map(|(frame, _, _)| *frame)
+            .or(self.latest.as_ref().map(|(frame, _, _)| *frame))
+    }
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.history.clear();
+    }
+
+    /** Whether `record` keeps a snapshot of `frame`, to skip making states that would be thrown away */
+    pub fn is_due(&self, frame: u64) -> bool {
+        frame.is_multiple_of(self.interval)
+    }
+    /** Takes a snapshot if `frame` falls on the interval, `cycles` being the cycle count to go back to with it */
+    pub fn record(&mut self, frame: u64, cycles: u64, state: Vec<u8>) {
+        if !self.is_due(frame) {
+            return;
+        }
+        if let Some((latest_frame, latest_cycles, latest_state)) = self.latest.take() {
+            self.history.push_back((
+                latest_frame,
+                latest_cycles,
+                Delta::encode(&state, &latest_state),
+            ));
+            if self.history.len() >= self.depth {
+                self.history.pop_front();
+            }
+        }
+        self.latest = Some((frame, cycles, state));
+    }
+
+    /** Drops snapshots newer than `frame` and returns the newest one left, or the oldest one if none is old enough */
+    /** Returns its frame, cycle count and save state */
+    pub fn rewind_to(&mut self, frame: u64) -> Option<(u64, u64, &[u8])> {
+        let (latest_frame, latest_cycles, latest_state) = self.latest.as_mut()?;
+        while *latest_frame > frame {
+            let Some((older_frame, older_cycles, delta)) = self.history.pop_back() else {
+                break;
+            };
+            *latest_state = delta.apply(latest_state);
+            *latest_frame = older_frame;
+            *latest_cycles = older_cycles;
+        }
+
+        Some((*latest_frame, *latest_cycles, latest_state.as_slice()))
+    }
+
+    /** Bytes held by all snapshots, roughly */
+    pub fn size(&self) -> usize {
+        let latest = self.latest.as_ref().map_or(0, |(_, _, state)| state.len());
+        latest
+            + self
+                .history
+                .iter()
+                .map(|(_, _, delta)| delta.size())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Delta, Rewind};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_delta() {
+        // Arrange
+        let base = vec![0u8; 100];
+        let mut target = base.clone();
+        target[3] = 1;
+        target[5] = 2;
+        target[90] = 3;
+        target.push(4);
+
+        // Act
+        let delta = Delta::encode(&base, &target);
+
+        // Assert
+        assert_eq!(delta.runs.len(), 3);
+        assert_eq!(delta.apply(&base), target);
+        assert_eq!(Delta::encode(&target, &base).apply(&target), base);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_is_due() {
+        // Arrange
+        let rewind = Rewind::new(10, 6);
+
+        // Act
+        let due: Vec<u64> = (0..13).filter(|&frame| rewind.is_due(frame)).collect();
+
+        // Assert
+        assert_eq!(due, [0, 6, 12]);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_rewind_to() {
+        // Arrange
+        let mut rewind = Rewind::new(3, 10);
+        for frame in 0..=40 {
+            rewind.record(frame, frame * 3, vec![frame as u8; 16]);
+        }
+
+        // Act
+        let oldest = rewind.oldest_frame();
+        let (frame, cycles, state) = rewind.rewind_to(25).unwrap();
+        let restored = (frame, cycles, state.to_vec());
+
+        // Assert
+        assert_eq!(oldest, Some(20));
+        assert_eq!(restored, (20, 60, vec![20; 16]));
+        assert_eq!(rewind.len(), 1);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::components::keypad::Keypad;
+use crate::components::memory::Memory;
+use crate::components::processor::{KeyTrigger, Processor, ProcessorState};
+use crate::components::rng::Rng;
+use crate::components::screen::{Resolution, Screen};
+use crate::components::stack::{OverflowPolicy, Stack};
+use crate::error::EmulatorError;
+use crate::quirks::Quirks;
+use std::error::Error;
+
+/** Identifies a save state file */
+pub const MAGIC: [u8; 4] = *b"C8SS";
+/** Bumped whenever the payload layout changes */
+pub const VERSION: u16 = 6;
+/** Magic, version, payload length and CRC-32 of the payload */
+pub const HEADER_LEN: usize = 4 + 2 + 4 + 4;
+
+/** Serializes the whole processor: registers, stack, timers, settings, keypad, RNG, RPL flags, XO-CHIP audio, memory and framebuffer */
+/** All numbers are little endian. `load` restores the saved keypad, see `Emulator::load_state_keep_input` to keep the live one */
+pub fn save(processor: &Processor) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(processor.memory.data.len() + processor.gfx.len() + 128);
+
+    payload.extend(processor.pc.to_le_bytes());
+    payload.extend(processor.i.to_le_bytes());
+    payload.push(processor.stack.depth() as u8);
+    payload.push(encode_overflow(processor.stack.overflow()));
+    payload.push(processor.stack.len() as u8);
+    for address in processor.stack.entries() {
+        payload.extend(address.to_le_bytes());
+    }
+    payload.extend(processor.v);
+    payload.push(processor.delay_timer);
+    payload.push(processor.sound_timer);
+    payload.push(processor.quirks.to_bits());
+    payload.push(encode_key_trigger(processor.key_trigger));
+    match processor.state {
+        ProcessorState::Running => payload.push(0),
+        ProcessorState::WaitingForVblank => payload.push(3),
+        ProcessorState::Exited => payload.push(4),
+        ProcessorState::WaitingForKey { x, key } => {
+            payload.extend([1, x as u8, key.unwrap_or(0xFF)])
+        }
+        ProcessorState::Halted(err) => {
+            let (kind, address) = match err {
+                EmulatorError::StackUnderflow { .. } => (0, 0),
+                EmulatorError::StackOverflow { .. } => (1, 0),
+                EmulatorError::MemoryOutOfBounds { address, .. } => (2, address),
+                EmulatorError::UnknownOpcode { .. } => (3, 0),
+                EmulatorError::RomTooLarge { size, .. } => (4, size),
+            };
+            payload.extend([2, kind]);
+            match err {
+                // Not a fault of an instruction, so `max` takes the place of the pc and opcode
+                EmulatorError::RomTooLarge { max, .. } => {
+                    payload.extend((max as u32).to_le_bytes())
+                }
+                _ => {
+                    payload.extend(err.pc().unwrap_or(0).to_le_bytes());
+                    payload.extend(err.opcode().unwrap_or(0).to_le_bytes());
+                }
+            }
+            payload.extend((address as u32).to_le_bytes());
+        }
+    }
+    payload.extend(processor.keypad.state().to_le_bytes());
+    payload.extend(processor.rng.seed().to_le_bytes());
+    payload.extend(processor.rng.state().to_le_bytes());
+    payload.extend(processor.rpl);
+    payload.push(match processor.resolution {
+        Resolution::Low => 0,
+        Resolution::High => 1,
+        Resolution::TwoPage => 2,
+    });
+    payload.push(processor.planes);
+    payload.push(processor.audio_pattern.is_some() as u8);
+    payload.extend(processor.audio_pattern.unwrap_or_default());
+    payload.push(processor.pitch);
+    payload.extend((processor.memory.data.len() as u32).to_le_bytes());
+    payload.extend(&processor.memory.data);
+    payload.extend(processor.gfx);
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend(MAGIC);
+    bytes.extend(VERSION.to_le_bytes());
+    bytes.extend((payload.len() as u32).to_le_bytes());
+    bytes.extend(crc32(&payload).to_le_bytes());
+    bytes.extend(payload);
+
+    bytes
+}
+
+/** Restores a state written by `save`. The processor is left untouched if the state is invalid */
+pub fn load(processor: &mut Processor, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+    if bytes.len() < HEADER_LEN || bytes[0..4] != MAGIC {
+        return Err("Not a save state".into());
+    }
+    let mut header = Reader::new(&bytes[4..HEADER_LEN]);
+    let version = header.u16()?;
+    let length = header.u32()? as usize;
+    let checksum = header.u32()?;
+    if version != VERSION {
+        return Err(format!("Unsupported save state version {}", version).into());
+    }
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() != length {
+        return Err("Save state is truncated".into());
+    }
+    if crc32(payload) != checksum {
+        return Err("Save state checksum mismatch".into());
+    }
+
+    let mut reader = Reader::new(payload);
+    let pc = reader.u16()?;
+    let i = reader.u16()?;
+    let stack_depth = reader.u8()? as usize;
+    let overflow = decode_overflow(reader.u8()?)?;
+    let stack_len = reader.u8()? as usize;
+    if stack_depth == 0 || stack_depth > Stack::MAX_DEPTH || stack_len > stack_depth {
+        return Err("Invalid stack".into());
+    }
+    let mut entries = [0; Stack::MAX_DEPTH];
+    for entry in entries.iter_mut() {
+        *entry = reader.u16()?;
+    }
+    let stack = Stack::from_parts(entries, stack_len, stack_depth, overflow);
+    let v = reader.array::<16>()?;
+    let delay_timer = reader.u8()?;
+    let sound_timer = reader.u8()?;
+    let quirks = match reader.u8()? {
+        bits if bits < 0x80 => Quirks::from_bits(bits),
+        other => return Err(format!("Invalid quirks {:#04X}", other).into()),
+    };
+    let key_trigger = decode_key_trigger(reader.u8()?)?;
+    let state = match reader.u8()? {
+        0 => ProcessorState::Running,
+        1 => match reader.array::<2>()? {
+            [x, key] if x < 16 => ProcessorState::WaitingForKey {
+                x: x as usize,
+                key: if key == 0xFF { None } else { Some(key & 0xF) },
+            },
+            _ => return Err("Invalid key wait".into()),
+        },
+        2 => {
+            let kind = reader.u8()?;
+            let pc = reader.u16()?;
+            let opcode = reader.u16()?;
+            let address = reader.u32()? as usize;
+            ProcessorState::Halted(match kind {
+                0 => EmulatorError::StackUnderflow { pc, opcode },
+                1 => EmulatorError::StackOverflow { pc, opcode },
+                2 => EmulatorError::MemoryOutOfBounds {
+                    pc,
+                    opcode,
+                    address,
+                },
+                3 => EmulatorError::UnknownOpcode { pc, opcode },
+                4 => EmulatorError::RomTooLarge {
+                    size: address,
+                    max: (opcode as usize) << 16 | pc as usize,
+                },
+                other => return Err(format!("Invalid fault {}", other).into()),
+            })
+        }
+        3 => ProcessorState::WaitingForVblank,
+        4 => ProcessorState::Exited,
+        other => return Err(format!("Invalid processor state {}", other).into()),
+    };
+    let mut keypad = Keypad::default();
+    keypad.set_state(reader.u16()?);
+    let mut rng = Rng::from_seed(reader.u64()?);
+    rng.set_state(reader.u64()?);
+    let rpl = reader.array::<16>()?;
+    let resolution = match reader.u8()? {
+        0 => Resolution::Low,
+        1 => Resolution::High,
+        2 => Resolution::TwoPage,
+        other => return Err(format!("Invalid resolution {}", other).into()),
+    };
+    let planes = reader.u8()?;
+    if planes > 3 {
+        return Err(format!("Invalid planes {}", planes).into());
+    }
+    let has_audio_pattern = reader.u8()? != 0;
+    let audio_pattern = reader.array::<16>()?;
+    let pitch = reader.u8()?;
+    let memory_size = reader.u32()? as usize;
+    if !(Memory::SIZE..=Memory::MAX_SIZE).contains(&memory_size) {
+        return Err(format!("Invalid memory size {}", memory_size).into());
+    }
+    let memory = reader.take(memory_size)?.to_vec();
+    let gfx = reader.array::<{ Screen::MAX_WIDTH * Screen::MAX_HEIGHT }>()?;
+    if !reader.is_empty() {
+        return Err("Save state has trailing data".into());
+    }
+
+    processor.pc = pc;
+    processor.i = i;
+    processor.stack = stack;
+    processor.v = v;
+    processor.delay_timer = delay_timer;
+    processor.sound_timer = sound_timer;
+    processor.quirks = quirks;
+    processor.key_trigger = key_trigger;
+    processor.state = state;
+    processor.keypad = keypad;
+    processor.rng = rng;
+    processor.rpl = rpl;
+    processor.resolution = resolution;
+    processor.planes = planes;
+    processor.audio_pattern = has_audio_pattern.then_some(audio_pattern);
+    processor.pitch = pitch;
+    processor.memory = Memory { data: memory };
+    processor.gfx = gfx;
+
+    Ok(())
+}
+
+/** Byte for a key trigger, shared with movies */
+pub(crate) fn encode_key_trigger(key_trigger: KeyTrigger) -> u8 {
+    match key_trigger {
+        KeyTrigger::Press => 0,
+        KeyTrigger::Release => 1,
+    }
+}
+pub(crate) fn decode_key_trigger(byte: u8) -> Result<KeyTrigger, Box<dyn Error>> {
+    match byte {
+        0 => Ok(KeyTrigger::Press),
+        1 => Ok(KeyTrigger::Release),
+        other => Err(format!("Invalid key trigger {}", other).into()),
+    }
+}
+/** Byte for a stack overflow policy, shared with movies */
+pub(crate) fn encode_overflow(overflow: OverflowPolicy) -> u8 {
+    match overflow {
+        OverflowPolicy::Fault => 0,
+        OverflowPolicy::Wrap => 1,
+        OverflowPolicy::Schip => 2,
+    }
+}
+pub(crate) fn decode_overflow(byte: u8) -> Result<OverflowPolicy, Box<dyn Error>> {
+    match byte {
+        0 => Ok(OverflowPolicy::Fault),
+        1 => Ok(OverflowPolicy::Wrap),
+        2 => Ok(OverflowPolicy::Schip),
+        other => Err(format!("Invalid stack overflow policy {}", other).into()),
+    }
+}
+
+/** CRC-32 as used by zip and PNG */
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/** Reads little endian numbers off the front of a byte slice */
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader { bytes }
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.bytes.len() < len {
+            return Err("Unexpected end of data".into());
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn Error>> {
+        Ok(self.take(N)?.try_into()?)
+    }
+    pub(crate) fn u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.take(1)?[0])
+    }
+    pub(crate) fn u16(&mut self) -> Result<u16, Box<dyn Error>> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+    pub(crate) fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+    pub(crate) fn u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quirks::Platform;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_round_trip() {
+        // Arrange
+        let mut processor = Processor::init_platform(Quirks::xo_chip(), Platform::xo_chip());
+        processor.pc = 0x246;
+        processor.i = 0x321;
+        processor.stack.push(0x202);
+        processor.stack.push(0x210);
+        processor.v[0xA] = 0x42;
+        processor.delay_timer = 0x12;
+        processor.sound_timer = 0x34;
+        processor.state = ProcessorState::WaitingForKey {
+            x: 3,
+            key: Some(0xB),
+        };
+        processor.keypad.press(0xB);
+        processor.rng = Rng::from_seed(99);
+        processor.rng.next_u8();
+        processor.memory.data[0xFFF] = 0x77;
+        processor.rpl[7] = 0x99;
+        processor.set_resolution(Resolution::High);
+        processor.gfx[100] = 1;
+
+        // Act
+        let bytes = save(&processor);
+        let mut restored = Processor::init();
+        load(&mut restored, &bytes).unwrap();
+        processor.state = ProcessorState::Halted(EmulatorError::MemoryOutOfBounds {
+            pc: 0x204,
+            opcode: 0xF355,
+            address: 0x1000,
+        });
+        let mut halted = Processor::init();
+        load(&mut halted, &save(&processor)).unwrap();
+
+        // Assert
+        assert_eq!(restored.pc, 0x246);
+        assert_eq!(restored.i, 0x321);
+        assert_eq!(restored.stack, processor.stack);
+        assert_eq!(restored.stack.as_slice(), [0x202, 0x210]);
+        assert_eq!(restored.v, processor.v);
+        assert_eq!((restored.delay_timer, restored.sound_timer), (0x12, 0x34));
+        assert_eq!(restored.quirks, Quirks::xo_chip());
+        assert_eq!(restored.key_trigger, KeyTrigger::Press);
+        assert_eq!(
+            restored.state,
+            ProcessorState::WaitingForKey {
+                x: 3,
+                key: Some(0xB)
+            }
+        );
+        assert_eq!(restored.keypad, processor.keypad);
+        assert_eq!(restored.rng, processor.rng);
+        assert_eq!(restored.memory.data, processor.memory.data);
+        assert_eq!(restored.rpl, processor.rpl);
+        assert_eq!(restored.resolution, Resolution::High);
+        assert_eq!(restored.gfx, processor.gfx);
+        assert_eq!(halted.state, processor.state);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_faults() {
+        for err in [
+            EmulatorError::StackUnderflow {
+                pc: 0x202,
+                opcode: 0x00EE,
+            },
+            EmulatorError::StackOverflow {
+                pc: 0x204,
+                opcode: 0x2204,
+            },
+            EmulatorError::MemoryOutOfBounds {
+                pc: 0x206,
+                opcode: 0xF355,
+                address: 0x1000,
+            },
+            EmulatorError::UnknownOpcode {
+                pc: 0x208,
+                opcode: 0xFFFF,
+            },
+            EmulatorError::RomTooLarge {
+                size: 0x20000,
+                max: 0x10000,
+            },
+        ] {
+            // Arrange
+            let mut processor = Processor::init();
+            processor.state = ProcessorState::Halted(err);
+            let mut restored = Processor::init();
+
+            // Act
+            load(&mut restored, &save(&processor)).unwrap();
+
+            // Assert
+            assert_eq!(restored.state, processor.state);
+        }
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_invalid() {
+        // Arrange
+        let mut processor = Processor::init();
+        processor.pc = 0x300;
+        let mut bytes = save(&processor);
+        let mut restored = Processor::init();
+
+        // Act
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let corrupted = load(&mut restored, &bytes);
+        let truncated = load(&mut restored, &bytes[..100]);
+        let garbage = load(&mut restored, b"hello");
+
+        // Assert
+        assert!(corrupted.unwrap_err().to_string().contains("checksum"));
+        assert!(truncated.is_err());
+        assert!(garbage.is_err());
+        assert_eq!(restored.pc, Memory::ROM_BEGIN_INDEX);
+    }
+}
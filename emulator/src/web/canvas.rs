@@ -0,0 +1,63 @@
+use crate::backend::VideoSink;
+use wasm_bindgen::JsCast;
+use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement};
+
+#[derive(Debug)]
+pub struct CanvasScreen {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+}
+impl CanvasScreen {
+    /** Dark, plane 1, plane 2 and both planes */
+    const PALETTE: [&'static str; 4] = ["#000", "#fff", "#f80", "#840"];
+
+    /** Takes over the first `<canvas>` on the page */
+    pub fn init(width: usize, height: usize) -> CanvasScreen {
+        let document = window().unwrap().document().unwrap();
+        let canvas_html_element = document
+            .query_selector("canvas")
+            .unwrap()
+            .expect("Canvas not found!");
+        let canvas = canvas_html_element
+            .dyn_into::<HtmlCanvasElement>()
+            .expect("Error casting canvas type!");
+
+        let context = canvas
+            .get_context("2d")
+            .unwrap()
+            .expect("Could not get canvas context!")
+            .dyn_into::<CanvasRenderingContext2d>()
+            .expect("Error casting canvas context type!");
+
+        let mut screen = CanvasScreen { canvas, context };
+        screen.resize(width, height);
+
+        screen
+    }
+
+    /** Matches the canvas to the resolution, the page's CSS keeps its size on screen */
+    fn resize(&mut self, width: usize, height: usize) {
+        self.canvas.set_width(width as u32);
+        self.canvas.set_height(height as u32);
+
+        self.context.set_fill_style_str("#000");
+        self.context
+            .fill_rect(0.0, 0.0, width as f64, height as f64);
+    }
+}
+impl VideoSink for CanvasScreen {
+    fn present(&mut self, gfx: &[u8], width: usize, height: usize) {
+        if self.canvas.width() != width as u32 || self.canvas.height() != height as u32 {
+            self.resize(width, height);
+        }
+
+        for row in 0..height {
+            for col in 0..width {
+                self.context.set_fill_style_str(
+                    CanvasScreen::PALETTE[gfx[row * width + col] as usize & 0x3],
+                );
+                self.context.fill_rect(col as f64, row as f64, 1.0, 1.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use super::{audio::AudioBeeper, canvas::CanvasScreen, keyboard::KeyboardInput};
+use crate::backend::{AudioSink, InputSource, VideoSink};
+use crate::emulator::Emulator;
+use crate::keymap::Keymap;
+use crate::movie::Movie;
+use crate::quirks::{Preset, Quirks};
+use log::*;
+use wasm_bindgen::prelude::*;
+
+/** Three minutes of rewind, a snapshot every 6 frames */
+const REWIND_DEPTH: usize = 1800;
+const REWIND_INTERVAL: u32 = 6;
+
+/** Wasm bindings: drives an `Emulator` with the canvas, audio element and keyboard */
+#[wasm_bindgen(js_name = Emulator)]
+pub struct WebEmulator {
+    emulator: Emulator,
+    /** Time not yet run as a whole 60 Hz frame, in milliseconds */
+    pending_ms: f64,
+    screen: CanvasScreen,
+    audio: AudioBeeper,
+    input: KeyboardInput,
+}
+
+#[wasm_bindgen(js_class = Emulator)]
+impl WebEmulator {
+    pub fn init(quirks: Quirks) -> WebEmulator {
+        WebEmulator::with_emulator(
+            Emulator::builder()
+                .quirks(quirks)
+                .rewind(REWIND_DEPTH, REWIND_INTERVAL)
+                .build(),
+        )
+    }
+    /** Same as `init`, but CXNN draws from a fixed seed so runs can be reproduced */
+    pub fn init_seeded(quirks: Quirks, seed: u64) -> WebEmulator {
+        WebEmulator::with_emulator(
+            Emulator::builder()
+                .quirks(quirks)
+                .seed(seed)
+                .rewind(REWIND_DEPTH, REWIND_INTERVAL)
+                .build(),
+        )
+    }
+    fn with_emulator(emulator: Emulator) -> WebEmulator {
+        WebEmulator {
+            screen: CanvasScreen::init(emulator.width(), emulator.height()),
+            audio: AudioBeeper::init(),
+            input: KeyboardInput::init(),
+            emulator,
+            pending_ms: 0.0,
+        }
+    }
+    pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), JsError> {
+        self.emulator.load_rom(rom)?;
+        Ok(())
+    }
+    /** Compiles and loads Octo source, see `Emulator::load_octo_source` */
+    pub fn load_octo_source(&mut self, source: &str) -> Result<(), JsError> {
+        self.emulator
+            .load_octo_source(source)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    /** Name of the `:breakpoint` at the instruction about to run, for the host to pause on */
+    pub fn breakpoint_name(&self) -> Option<String> {
+        self.emulator
+            .breakpoint()
+            .map(|breakpoint| breakpoint.name.clone())
+    }
+    pub fn quirks(&self) -> Quirks {
+        self.emulator.quirks()
+    }
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.emulator.set_quirks(quirks);
+    }
+    /** Quirks and machine of a preset named like for `Quirks.preset`, e.g. 64 KB of memory for `xochip` from the next `load_rom` */
+    pub fn set_preset(&mut self, name: &str) -> Result<(), JsError> {
+        let preset = Preset::from_name(name).map_err(|err| JsError::new(&err.to_string()))?;
+        self.emulator.set_quirks(preset.quirks());
+        self.emulator.set_platform(preset.platform());
+        Ok(())
+    }
+    pub fn load_address(&self) -> u16 {
+        self.emulator.load_address()
+    }
+    /** 0x600 for ETI-660 programs, takes effect on the next `load_rom` */
+    pub fn set_load_address(&mut self, address: u16) {
+        self.emulator.set_load_address(address);
+    }
+    /** Quick-save, see `Emulator::save_state` */
+    pub fn save_state(&self) -> Vec<u8> {
+        self.emulator.save_state()
+    }
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), JsError> {
+        self.emulator
+            .load_state(bytes)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    /** Quick-load: like `load_state`, but keys held on the keyboard stay held, see `Emulator::load_state_keep_input` */
+    pub fn load_state_keep_input(&mut self, bytes: &[u8]) -> Result<(), JsError> {
+        self.emulator
+            .load_state_keep_input(bytes)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    /** Loads the ROM and records the keypad from then on, see `Emulator::start_recording` */
+    pub fn start_recording(&mut self, rom: Vec<u8>) -> Result<(), JsError> {
+        self.emulator.start_recording(rom)?;
+        Ok(())
+    }
+    /** The recorded movie file, if recording */
+    pub fn stop_recording(&mut self) -> Option<Vec<u8>> {
+        self.emulator.stop_recording().map(|movie| movie.to_bytes())
+    }
+    pub fn is_recording(&self) -> bool {
+        self.emulator.is_recording()
+    }
+    pub fn play_movie(&mut self, bytes: &[u8], rom: Vec<u8>) -> Result<(), JsError> {
+        let movie = Movie::from_bytes(bytes).map_err(|err| JsError::new(&err.to_string()))?;
+        self.emulator
+            .play_movie(movie, rom)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+    pub fn is_playing_movie(&self) -> bool {
+        self.emulator.is_playing_movie()
+    }
+
+    /** Goes back at least `frames` frames, see `Emulator::rewind`. Returns how many frames were undone */
+    pub fn rewind(&mut self, frames: u32) -> u32 {
+        self.pending_ms = 0.0;
+        self.emulator.rewind(frames as u64) as u32
+    }
+    /** Seed of the CXNN random number generator, a BigInt on the JS side */
+    pub fn seed(&self) -> u64 {
+        self.emulator.seed()
+    }
+
+    /** Presses a hex key on this instance only, e.g. from an on-screen keypad */
+    pub fn key_down(&mut self, key: u8) {
+        self.emulator.key_down(key);
+    }
+    pub fn key_up(&mut self, key: u8) {
+        self.emulator.key_up(key);
+    }
+    /** Binds a `KeyboardEvent.code` (or typed character) to a hex key, keeping existing bindings */
+    pub fn bind_key(&mut self, host_key: &str, key: u8) {
+        let mut keymap = self.input.keymap();
+        keymap.bind(host_key, key);
+        self.input.set_keymap(keymap);
+    }
+    pub fn unbind_key(&mut self, host_key: &str) {
+        let mut keymap = self.input.keymap();
+        keymap.unbind(host_key);
+        self.input.set_keymap(keymap);
+    }
+    /** One of `qwerty`, `azerty` or `dvorak` */
+    pub fn set_keymap_preset(&mut self, name: &str) -> Result<(), JsError> {
+        let keymap = Keymap::preset(name).map_err(|err| JsError::new(&err.to_string()))?;
+        self.input.set_keymap(keymap);
+        Ok(())
+    }
+    /** Restores the physical 1234/QWER/ASDF/ZXCV layout */
+    pub fn reset_keymap(&mut self) {
+        self.input.set_keymap(Keymap::default());
+    }
+    pub fn keymap_json(&self) -> String {
+        self.input.keymap().to_json()
+    }
+    pub fn set_keymap_json(&mut self, json: &str) -> Result<(), JsError> {
+        let keymap = Keymap::from_json(json).map_err(|err| JsError::new(&err.to_string()))?;
+        self.input.set_keymap(keymap);
+        Ok(())
+    }
+
+    /** Whether the program is blocked on FX0A until a key is entered */
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.emulator.is_waiting_for_key()
+    }
+    /** Pressed keys as a bit mask, bit 0 is key 0x0 and bit 15 is key 0xF */
+    pub fn keypad_state(&self) -> u16 {
+        self.emulator.keypad_state()
+    }
+
+    /** Runs as many 60 Hz frames as fit into the time since the last call */
+    /** Throws once the emulator halts on a fault, and on every call after that until a ROM or state is loaded */
+    pub fn update(&mut self, elapsed_ms: f64) -> Result<(), JsError> {
+        const FRAME_MS: f64 = 1000.0 / 60.0;
+        // Don't try to catch up after the tab was in the background
+        self.pending_ms = (self.pending_ms + elapsed_ms).min(FRAME_MS * 4.0);
+
+        while self.pending_ms >= FRAME_MS {
+            self.pending_ms -= FRAME_MS;
+            self.run_frame()?;
+        }
+
+        Ok(())
+    }
+    pub fn run_frame(&mut self) -> Result<(), JsError> {
+        self.input.poll(self.emulator.keypad_mut());
+        let result = self.emulator.run_frame();
+        self.update_audio();
+
+        Ok(result?)
+    }
+    pub fn cycle(&mut self) -> Result<(), JsError> {
+        self.input.poll(self.emulator.keypad_mut());
+        let result = self.emulator.cycle();
+        self.update_audio();
+
+        Ok(result?)
+    }
+    fn update_audio(&mut self) {
+        match self.emulator.audio_pattern() {
+            Some((pattern, sample_rate)) => self.audio.set_pattern(&pattern, sample_rate),
+            None => self.audio.clear_pattern(),
+        }
+        self.audio
+            .set_playing(!self.emulator.is_halted() && self.emulator.is_sound_playing());
+    }
+    pub fn is_halted(&self) -> bool {
+        self.emulator.is_halted()
+    }
+    pub fn is_exited(&self) -> bool {
+        self.emulator.is_exited()
+    }
+    pub fn width(&self) -> usize {
+        self.emulator.width()
+    }
+    pub fn height(&self) -> usize {
+        self.emulator.height()
+    }
+    /** All 16 stack levels as a Uint16Array, of which the first `stack_len` are in use */
+    pub fn stack_entries(&self) -> Vec<u16> {
+        self.emulator.stack().entries().to_vec()
+    }
+    pub fn stack_len(&self) -> usize {
+        self.emulator.stack().len()
+    }
+    pub fn tick_timers(&mut self) {
+        self.emulator.tick_timers();
+    }
+    pub fn draw(&mut self) {
+        self.screen.present(
+            self.emulator.framebuffer(),
+            self.emulator.width(),
+            self.emulator.height(),
+        );
+    }
+}
+
+#[wasm_bindgen]
+pub fn init() {
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    console_log::init_with_level(Level::Warn).expect("Failed initializing logger!");
+}
@@ -0,0 +1,98 @@
+use crate::backend::InputSource;
+use crate::components::keypad::Keypad;
+use crate::keymap::Keymap;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use web_sys::{window, Document, KeyboardEvent};
+
+/** Listens for `keydown`/`keyup` on the document and maps them to CHIP-8 keys through a `Keymap` */
+pub struct KeyboardInput {
+    document: Document,
+    keymap: Rc<RefCell<Keymap>>,
+    /** Keys held on the keyboard, as a `Keypad` bit mask */
+    pressed: Rc<Cell<u16>>,
+    /** Mask applied by the last poll, so keys pressed through other means are left alone */
+    polled: u16,
+    on_key_down: Closure<dyn FnMut(KeyboardEvent)>,
+    on_key_up: Closure<dyn FnMut(KeyboardEvent)>,
+}
+impl KeyboardInput {
+    pub fn init() -> KeyboardInput {
+        let document = window().unwrap().document().unwrap();
+        let keymap = Rc::new(RefCell::new(Keymap::default()));
+        let pressed = Rc::new(Cell::new(0));
+
+        let on_key_down = {
+            let keymap = keymap.clone();
+            let pressed = pressed.clone();
+            Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                if let Some(key) = KeyboardInput::map_event(&keymap.borrow(), &event) {
+                    event.prevent_default();
+                    pressed.set(pressed.get() | 1 << key);
+                }
+            })
+        };
+        let on_key_up = {
+            let keymap = keymap.clone();
+            let pressed = pressed.clone();
+            Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+                if let Some(key) = KeyboardInput::map_event(&keymap.borrow(), &event) {
+                    pressed.set(pressed.get() & !(1 << key));
+                }
+            })
+        };
+
+        document
+            .add_event_listener_with_callback("keydown", on_key_down.as_ref().unchecked_ref())
+            .expect("Failed to add keydown listener!");
+        document
+            .add_event_listener_with_callback("keyup", on_key_up.as_ref().unchecked_ref())
+            .expect("Failed to add keyup listener!");
+
+        KeyboardInput {
+            document,
+            keymap,
+            pressed,
+            polled: 0,
+            on_key_down,
+            on_key_up,
+        }
+    }
+
+    pub fn keymap(&self) -> Keymap {
+        self.keymap.borrow().clone()
+    }
+    /** Replaces the keymap and releases keys held under the old one */
+    pub fn set_keymap(&self, keymap: Keymap) {
+        *self.keymap.borrow_mut() = keymap;
+        self.pressed.set(0);
+    }
+
+    /** Physical key position first, then the typed character */
+    fn map_event(keymap: &Keymap, event: &KeyboardEvent) -> Option<u8> {
+        keymap
+            .lookup(&event.code())
+            .or_else(|| keymap.lookup(&event.key()))
+    }
+}
+impl InputSource for KeyboardInput {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        let pressed = self.pressed.get();
+        let changed = pressed ^ self.polled;
+
+        keypad.set_state(keypad.state() & !changed | pressed & changed);
+        self.polled = pressed;
+    }
+}
+impl Drop for KeyboardInput {
+    fn drop(&mut self) {
+        let _ = self.document.remove_event_listener_with_callback(
+            "keydown",
+            self.on_key_down.as_ref().unchecked_ref(),
+        );
+        let _ = self
+            .document
+            .remove_event_listener_with_callback("keyup", self.on_key_up.as_ref().unchecked_ref());
+    }
+}
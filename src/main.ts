@@ -1,21 +1,18 @@
 import "./style.css";
 import * as wasm from "chip8-emulator";
-import { Emulator } from "chip8-emulator";
-import "./fasterInterval.js";
+import { Emulator, Quirks } from "chip8-emulator";
 
 const WIDTH = 64;
-const HEIGHT = 32;
 const SCALE = 10;
 
 const canvas = document.querySelector("canvas")!;
 canvas.style.width = WIDTH * SCALE + "px";
-canvas.style.height = HEIGHT * SCALE + "px";
 
 wasm.init();
-document.onkeydown = e => wasm.on_key_down(e.code);
-document.onkeyup = () => wasm.on_key_up();
 
-const emulator = Emulator.init(1);
+const selectedQuirks = document.getElementById("quirks")! as HTMLSelectElement;
+const emulator = Emulator.init(Quirks.preset(selectedQuirks.value));
+emulator.set_preset(selectedQuirks.value);
 
 const selectedRom = document.getElementById("rom")! as HTMLSelectElement;
 selectedRom.onchange = async () => {
@@ -25,9 +22,46 @@ selectedRom.onchange = async () => {
 
 document.getElementById("reload")!.onclick = async () => await loadRom();
 
+const selectedLayout = document.getElementById("layout")! as HTMLSelectElement;
+selectedLayout.onchange = () => {
+	selectedLayout.blur();
+	applyKeymap();
+};
+
+// Reloading applies the memory size of the new preset, 64 KB for XO-CHIP
+selectedQuirks.onchange = async () => {
+	selectedQuirks.blur();
+	emulator.set_preset(selectedQuirks.value);
+	await loadRom();
+};
+
+// Holding backspace rewinds, one snapshot per display frame
+let rewinding = false;
+
+// Quick-save with F6 and quick-load with F9, one slot per ROM until the page is reloaded
+const quickSaves = new Map<string, Uint8Array>();
+document.addEventListener("keydown", (event) => {
+	if (selectedRom.value === "") return;
+	if (event.code === "F6") {
+		event.preventDefault();
+		quickSaves.set(selectedRom.value, emulator.save_state());
+	} else if (event.code === "Backspace") {
+		event.preventDefault();
+		rewinding = true;
+	} else if (event.code === "F9") {
+		event.preventDefault();
+		const state = quickSaves.get(selectedRom.value);
+		if (state !== undefined) emulator.load_state_keep_input(state);
+	}
+});
+
+document.addEventListener("keyup", (event) => {
+	if (event.code === "Backspace") rewinding = false;
+});
+
 await loadRom();
-setInterval(cycle, 2);
-draw();
+let lastFrame = performance.now();
+requestAnimationFrame(frame);
 
 async function loadRom() {
 	if (selectedRom.value === "") return;
@@ -35,13 +69,42 @@ async function loadRom() {
 	const response = await fetch(`roms/${selectedRom.value}.ch8`);
 	const data = await response.arrayBuffer();
 	emulator.load_rom(new Uint8Array(data));
+	applyKeymap();
 }
 
-function cycle() {
-	if (selectedRom.value === "") return;
-	emulator.cycle();
+// A keymap saved for the ROM, e.g. localStorage["keymap:pong2"] = emulator.keymap_json(), wins over the layout
+function applyKeymap() {
+	const romKeymap = localStorage.getItem(`keymap:${selectedRom.value}`);
+	if (romKeymap !== null) emulator.set_keymap_json(romKeymap);
+	else if (selectedLayout.value === "") emulator.reset_keymap();
+	else emulator.set_keymap_preset(selectedLayout.value);
 }
-function draw() {
+
+// The emulator runs whole 60 Hz frames for the elapsed time, whatever the display refresh rate
+function frame(now: number) {
+	if (selectedRom.value !== "") {
+		if (rewinding) emulator.rewind(1);
+		else if (!emulator.is_halted()) {
+			// A fault halts the emulator until the ROM is reloaded or rewound
+			try {
+				emulator.update(now - lastFrame);
+			} catch (err) {
+				console.error(err);
+			}
+		}
+	}
+	lastFrame = now;
+
 	emulator.draw();
-	return requestAnimationFrame(draw);
+	resizeCanvas();
+	return requestAnimationFrame(frame);
+}
+
+// The canvas keeps its width on screen and takes the aspect of the current resolution, e.g. square for 64x64 ROMs
+let aspect = 0;
+function resizeCanvas() {
+	const current = emulator.height() / emulator.width();
+	if (current === aspect) return;
+	aspect = current;
+	canvas.style.height = WIDTH * SCALE * aspect + "px";
 }